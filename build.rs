@@ -0,0 +1,4 @@
+// Embedded migrations are read at compile time, so new files must trigger a rebuild.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
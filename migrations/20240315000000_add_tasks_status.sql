@@ -0,0 +1,5 @@
+CREATE TYPE task_status AS ENUM ('todo', 'in_progress', 'blocked', 'done', 'cancelled');
+
+ALTER TABLE tasks
+    ADD COLUMN status task_status NOT NULL DEFAULT 'todo',
+    ADD COLUMN completed_at TIMESTAMPTZ;
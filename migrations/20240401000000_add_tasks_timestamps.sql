@@ -0,0 +1,7 @@
+ALTER TABLE tasks
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN due_at TIMESTAMPTZ,
+    ADD COLUMN start_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS tasks_due_at_idx ON tasks (due_at) WHERE deleted_at IS NULL;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS tags (
+    tag_id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    color VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS tags_name_key ON tags (LOWER(name));
+
+CREATE TABLE IF NOT EXISTS task_tags (
+    task_id INTEGER NOT NULL REFERENCES tasks (task_id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (tag_id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS task_tags_tag_id_idx ON task_tags (tag_id);
@@ -0,0 +1,47 @@
+ALTER TABLE tasks
+    ADD COLUMN parent_task_id INTEGER REFERENCES tasks (task_id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tasks_parent_task_id_idx ON tasks (parent_task_id);
+
+-- A parent has to be a live task that is neither the task itself nor one of its subtasks.
+-- Hierarchy changes are serialized, so two concurrent moves cannot form a cycle together.
+CREATE FUNCTION check_task_parent() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('tasks.parent_task_id'));
+
+    IF NOT EXISTS (
+        SELECT 1 FROM tasks WHERE task_id = NEW.parent_task_id AND deleted_at IS NULL
+    ) THEN
+        RAISE EXCEPTION 'Parent task % does not exist', NEW.parent_task_id
+            USING ERRCODE = 'foreign_key_violation', CONSTRAINT = 'tasks_parent_task_id_fkey';
+    END IF;
+
+    IF EXISTS (
+        WITH RECURSIVE ancestors AS (
+            SELECT NEW.parent_task_id AS task_id
+            UNION
+            SELECT tasks.parent_task_id
+            FROM tasks JOIN ancestors USING (task_id)
+            WHERE tasks.parent_task_id IS NOT NULL
+        )
+        SELECT 1 FROM ancestors WHERE task_id = NEW.task_id
+    ) THEN
+        RAISE EXCEPTION 'Task % cannot be a subtask of itself', NEW.task_id
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'tasks_parent_task_id_acyclic';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tasks_parent_on_insert
+    BEFORE INSERT ON tasks
+    FOR EACH ROW
+    WHEN (NEW.parent_task_id IS NOT NULL)
+    EXECUTE FUNCTION check_task_parent();
+
+CREATE TRIGGER tasks_parent_on_update
+    BEFORE UPDATE OF parent_task_id ON tasks
+    FOR EACH ROW
+    WHEN (NEW.parent_task_id IS NOT NULL AND NEW.parent_task_id IS DISTINCT FROM OLD.parent_task_id)
+    EXECUTE FUNCTION check_task_parent();
@@ -0,0 +1,36 @@
+CREATE TABLE IF NOT EXISTS task_dependencies (
+    task_id INTEGER NOT NULL REFERENCES tasks (task_id) ON DELETE CASCADE,
+    blocker_task_id INTEGER NOT NULL REFERENCES tasks (task_id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, blocker_task_id)
+);
+
+CREATE INDEX IF NOT EXISTS task_dependencies_blocker_task_id_idx
+    ON task_dependencies (blocker_task_id);
+
+-- A task cannot be blocked by itself or by a task it blocks, directly or not.
+-- Dependency changes are serialized, so two concurrent edges cannot form a cycle together.
+CREATE FUNCTION check_task_dependency() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('task_dependencies'));
+
+    IF EXISTS (
+        WITH RECURSIVE blockers AS (
+            SELECT NEW.blocker_task_id AS task_id
+            UNION
+            SELECT task_dependencies.blocker_task_id
+            FROM task_dependencies JOIN blockers USING (task_id)
+        )
+        SELECT 1 FROM blockers WHERE task_id = NEW.task_id
+    ) THEN
+        RAISE EXCEPTION 'Task % cannot be blocked by task %', NEW.task_id, NEW.blocker_task_id
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'task_dependencies_acyclic';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_dependencies_on_insert
+    BEFORE INSERT ON task_dependencies
+    FOR EACH ROW
+    EXECUTE FUNCTION check_task_dependency();
@@ -0,0 +1,2 @@
+-- Backs full-text search over task names. Queries have to use the exact same expression.
+CREATE INDEX IF NOT EXISTS tasks_name_search_idx ON tasks USING GIN (to_tsvector('simple', name));
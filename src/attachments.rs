@@ -0,0 +1,183 @@
+use std::{
+    collections::HashSet,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use axum::extract::multipart::{Field, MultipartError};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::{
+    blob_store::{BlobStream, SharedBlobStore},
+    repository::SharedTaskRepository,
+};
+
+pub const DEFAULT_MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Room left in a request body for the form around the file: boundaries, headers and
+/// other fields.
+const FORM_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// Bytes at the start of a file that its type is detected from.
+const SNIFF_LENGTH: usize = 8192;
+
+/// Longest file name kept, in characters.
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// What is known of an upload while it streams through to the blob store.
+#[derive(Default)]
+pub struct UploadProgress {
+    pub size: u64,
+    /// The first bytes of the file, to detect its type from.
+    pub head: Vec<u8>,
+    /// Why the rest of the request body could not be read, when it could not.
+    pub body_error: Option<MultipartError>,
+}
+
+/// Largest request body an upload of files up to `max_attachment_bytes` may need.
+pub fn body_limit(max_attachment_bytes: u64) -> usize {
+    usize::try_from(max_attachment_bytes)
+        .unwrap_or(usize::MAX)
+        .saturating_add(FORM_OVERHEAD_BYTES)
+}
+
+/// Directory of the blob store every attachment file is kept below, so that the store
+/// can be shared with other data.
+pub const STORAGE_PREFIX: &str = "tasks";
+
+/// A new, unique key for a file of `task_id`.
+pub fn storage_key(task_id: i32) -> String {
+    format!("{STORAGE_PREFIX}/{task_id}/{}", Uuid::new_v4())
+}
+
+/// Streams the contents of `field`, failing once it grows past `max_size` bytes, while
+/// recording its size and first bytes in the returned progress.
+pub fn stream_field(
+    field: Field<'_>,
+    max_size: u64,
+) -> (BlobStream<'_>, Arc<Mutex<UploadProgress>>) {
+    let progress = Arc::new(Mutex::new(UploadProgress::default()));
+    let recorded = progress.clone();
+
+    let stream = field.map(move |chunk| {
+        let mut progress = recorded.lock().unwrap();
+
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                progress.body_error = Some(err);
+                return Err(io::Error::other("The request body could not be read"));
+            }
+        };
+
+        progress.size += chunk.len() as u64;
+        if progress.size > max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                "The file is too large",
+            ));
+        }
+
+        let missing = SNIFF_LENGTH.saturating_sub(progress.head.len());
+        progress
+            .head
+            .extend_from_slice(&chunk[..missing.min(chunk.len())]);
+
+        Ok(chunk)
+    });
+
+    (stream.boxed(), progress)
+}
+
+/// The media type of a file starting with `head`, detected from its magic bytes. Text that
+/// is not recognized otherwise is `text/plain` and anything else `application/octet-stream`.
+pub fn sniff_content_type(head: &[u8]) -> String {
+    if let Some(kind) = infer::get(head) {
+        return kind.mime_type().to_owned();
+    }
+
+    // `head` may end in the middle of a character.
+    let is_text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    };
+
+    if is_text && !head.contains(&0) {
+        "text/plain; charset=utf-8".to_owned()
+    } else {
+        "application/octet-stream".to_owned()
+    }
+}
+
+/// The last component of a client-supplied file name, without control characters or
+/// quotes, or `attachment` when nothing is left.
+pub fn clean_file_name(file_name: Option<&str>) -> String {
+    let base_name = file_name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default();
+
+    let cleaned: String = base_name
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+
+    match cleaned.trim() {
+        "" | "." | ".." => "attachment".to_owned(),
+        trimmed => trimmed.to_owned(),
+    }
+}
+
+/// `Content-Disposition` of a download, with an ASCII fallback for non-ASCII names.
+pub fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+
+    let encoded: String = file_name
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect();
+
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// Deletes the blobs below [`STORAGE_PREFIX`] no attachment refers to anymore, such as the
+/// files of deleted tasks, returning how many were deleted. Blobs written after
+/// `written_before` are kept, since their upload may still be under way.
+pub async fn sweep_orphaned_blobs(
+    repository: &SharedTaskRepository,
+    blob_store: &SharedBlobStore,
+    written_before: DateTime<Utc>,
+) -> io::Result<u64> {
+    // Listed first, so that blobs recorded in between are not mistaken for orphans.
+    let blobs = blob_store.list(STORAGE_PREFIX).await?;
+
+    let referenced: HashSet<String> = repository
+        .attachment_storage_keys()
+        .await
+        .map_err(io::Error::other)?
+        .into_iter()
+        .collect();
+
+    let mut deleted = 0;
+
+    for blob in blobs {
+        if blob.last_modified < written_before && !referenced.contains(&blob.key) {
+            blob_store.delete(&blob.key).await?;
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
@@ -0,0 +1,115 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt};
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::{BlobInfo, BlobStore, BlobStream};
+
+/// Suffix of the file a blob is written to before it is complete.
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Keeps blobs as files below a directory, with the slashes of their keys as subdirectories.
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, body: BlobStream<'_>) -> io::Result<()> {
+        let path = self.path_of(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Readers never see a half-written blob: it only gets its name once complete.
+        let partial_path = PathBuf::from(format!("{}{PARTIAL_SUFFIX}", path.display()));
+
+        match write_file(&partial_path, body).await {
+            Ok(()) => fs::rename(&partial_path, &path).await,
+            Err(err) => {
+                let _ = fs::remove_file(&partial_path).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Option<BlobStream<'static>>> {
+        match fs::File::open(self.path_of(key)).await {
+            Ok(file) => Ok(Some(ReaderStream::new(file).boxed())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path_of(key)).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> io::Result<Vec<BlobInfo>> {
+        let mut blobs = Vec::new();
+        let mut pending = vec![self.path_of(prefix)];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                } else {
+                    blobs.push(BlobInfo {
+                        key: key_of(&self.root, &entry.path()),
+                        last_modified: DateTime::<Utc>::from(metadata.modified()?),
+                    });
+                }
+            }
+        }
+
+        Ok(blobs)
+    }
+}
+
+async fn write_file(path: &Path, mut body: BlobStream<'_>) -> io::Result<()> {
+    let mut file = fs::File::create(path).await?;
+
+    while let Some(chunk) = body.try_next().await? {
+        file.write_all(&chunk).await?;
+    }
+
+    file.sync_all().await
+}
+
+/// The key of the file at `path`, with `/` separators whatever the platform.
+fn key_of(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
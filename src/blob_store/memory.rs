@@ -0,0 +1,67 @@
+use std::{collections::BTreeMap, io, sync::RwLock};
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+use super::{BlobInfo, BlobStore, BlobStream};
+
+/// Keeps blobs in process memory, for tests and local demos.
+#[derive(Default)]
+pub struct InMemoryBlobStore {
+    blobs: RwLock<BTreeMap<String, (Bytes, DateTime<Utc>)>>,
+}
+
+impl InMemoryBlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BlobStore for InMemoryBlobStore {
+    async fn put(&self, key: &str, body: BlobStream<'_>) -> io::Result<()> {
+        let contents = body
+            .try_fold(BytesMut::new(), |mut contents, chunk| async move {
+                contents.extend_from_slice(&chunk);
+                Ok(contents)
+            })
+            .await?;
+
+        self.blobs
+            .write()
+            .unwrap()
+            .insert(key.to_owned(), (contents.freeze(), Utc::now()));
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Option<BlobStream<'static>>> {
+        let blobs = self.blobs.read().unwrap();
+
+        Ok(blobs
+            .get(key)
+            .map(|(contents, _)| stream::once(std::future::ready(Ok(contents.clone()))).boxed()))
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        self.blobs.write().unwrap().remove(key);
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> io::Result<Vec<BlobInfo>> {
+        let blobs = self.blobs.read().unwrap();
+        let prefix = format!("{prefix}/");
+
+        Ok(blobs
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(key, (_, last_modified))| BlobInfo {
+                key: key.clone(),
+                last_modified: *last_modified,
+            })
+            .collect())
+    }
+}
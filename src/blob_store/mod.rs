@@ -0,0 +1,45 @@
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+
+mod local;
+mod memory;
+mod s3;
+
+pub use local::LocalBlobStore;
+pub use memory::InMemoryBlobStore;
+pub use s3::S3BlobStore;
+
+/// Contents of a blob, read or written a chunk at a time.
+pub type BlobStream<'a> = BoxStream<'a, io::Result<Bytes>>;
+
+/// A stored blob, as listed by [`BlobStore::list`].
+pub struct BlobInfo {
+    pub key: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// Holds the files of attachments, addressed by keys such as `tasks/1/<uuid>`, while
+/// their metadata stays in the [`crate::repository::TaskRepository`].
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Streams `body` into the blob under `key`, replacing any previous one. Fails with the
+    /// first error of `body`, if any, in which case nothing is left under `key`.
+    async fn put(&self, key: &str, body: BlobStream<'_>) -> io::Result<()>;
+
+    /// Streams the blob under `key`, or returns `None` when there is none.
+    async fn get(&self, key: &str) -> io::Result<Option<BlobStream<'static>>>;
+
+    /// Deletes the blob under `key`; deleting a missing blob is not an error.
+    async fn delete(&self, key: &str) -> io::Result<()>;
+
+    /// Every stored blob whose key is below the `prefix` directory, such as `tasks`,
+    /// including the leftovers of interrupted uploads. Blobs elsewhere in a shared bucket
+    /// or directory are left out.
+    async fn list(&self, prefix: &str) -> io::Result<Vec<BlobInfo>>;
+}
+
+pub type SharedBlobStore = Arc<dyn BlobStore>;
@@ -0,0 +1,83 @@
+use std::io;
+
+use async_trait::async_trait;
+use futures_util::{StreamExt, TryStreamExt};
+use object_store::{ObjectStore, WriteMultipart, aws::AmazonS3, path::Path};
+
+use super::{BlobInfo, BlobStore, BlobStream};
+
+/// Parts of a multipart upload that may be in flight at once.
+const MAX_CONCURRENT_PARTS: usize = 4;
+
+/// Keeps blobs as objects of an S3 bucket, or of any S3-compatible store such as MinIO.
+pub struct S3BlobStore {
+    bucket: AmazonS3,
+}
+
+impl S3BlobStore {
+    pub fn new(bucket: AmazonS3) -> Self {
+        Self { bucket }
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, mut body: BlobStream<'_>) -> io::Result<()> {
+        // A multipart upload keeps memory bounded whatever the size of the blob, and is
+        // only made visible by `finish`.
+        let upload = self
+            .bucket
+            .put_multipart(&Path::from(key))
+            .await
+            .map_err(io::Error::other)?;
+        let mut writer = WriteMultipart::new(upload);
+
+        let written = async {
+            while let Some(chunk) = body.try_next().await? {
+                writer
+                    .wait_for_capacity(MAX_CONCURRENT_PARTS)
+                    .await
+                    .map_err(io::Error::other)?;
+                writer.put(chunk);
+            }
+
+            Ok(())
+        }
+        .await;
+
+        match written {
+            Ok(()) => writer.finish().await.map(drop).map_err(io::Error::other),
+            Err(err) => {
+                let _ = writer.abort().await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Option<BlobStream<'static>>> {
+        match self.bucket.get(&Path::from(key)).await {
+            Ok(result) => Ok(Some(result.into_stream().map_err(io::Error::other).boxed())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match self.bucket.delete(&Path::from(key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> io::Result<Vec<BlobInfo>> {
+        self.bucket
+            .list(Some(&Path::from(prefix)))
+            .map_ok(|object| BlobInfo {
+                key: object.location.to_string(),
+                last_modified: object.last_modified,
+            })
+            .map_err(io::Error::other)
+            .try_collect()
+            .await
+    }
+}
@@ -0,0 +1,493 @@
+use std::{fmt, fs, io, path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachments::DEFAULT_MAX_ATTACHMENT_BYTES,
+    shutdown::DrainOptions,
+    state::{DeleteMode, SubtasksOnDelete},
+    workflow::Transitions,
+};
+
+/// Default location of the optional config file, used when `--config` is not given.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Command line of the binary. Every setting can also come from the environment
+/// or the config file, with the precedence: flag > environment variable > file > default.
+#[derive(Parser)]
+#[command(version, about = "Tasks CRUD REST API", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// TOML config file [default: config.toml, when it exists]
+    #[arg(long, env = "CONFIG_FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Print the effective configuration and exit
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
+    #[command(flatten)]
+    overrides: ConfigOverrides,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Serve the API (the default)
+    Serve,
+    /// List the embedded migrations and whether they are applied
+    Migrations,
+    /// Permanently remove tasks that have been in the trash for longer than the retention,
+    /// then the attachment files nothing refers to anymore
+    Purge,
+}
+
+#[derive(clap::Args, Clone)]
+struct ConfigOverrides {
+    /// Address the server binds to
+    #[arg(long, env = "SERVER_ADDRESS", global = true)]
+    address: Option<String>,
+
+    /// Seconds to keep serving, with readiness failing, after a shutdown signal
+    #[arg(long, env = "SHUTDOWN_DELAY_SECS", global = true)]
+    shutdown_delay_secs: Option<u64>,
+
+    /// Seconds in-flight requests get to finish during shutdown
+    #[arg(long, env = "DRAIN_TIMEOUT_SECS", global = true)]
+    drain_timeout_secs: Option<u64>,
+
+    /// Seconds the database pool gets to close after the drain, abandoning connections
+    /// still in use
+    #[arg(long, env = "POOL_CLOSE_TIMEOUT_SECS", global = true)]
+    pool_close_timeout_secs: Option<u64>,
+
+    /// Where tasks are stored
+    #[arg(long, env = "STORAGE_BACKEND", global = true)]
+    storage: Option<StorageBackend>,
+
+    /// Postgres connection URL
+    #[arg(long, env = "DATABASE_URL", global = true, hide_env_values = true)]
+    database_url: Option<String>,
+
+    /// Maximum number of pooled database connections
+    #[arg(long, env = "DATABASE_MAX_CONNECTIONS", global = true)]
+    max_connections: Option<u32>,
+
+    /// Number of database connections kept open even when idle
+    #[arg(long, env = "DATABASE_MIN_CONNECTIONS", global = true)]
+    min_connections: Option<u32>,
+
+    /// Seconds to wait for a pooled connection before failing the request
+    #[arg(long, env = "DATABASE_ACQUIRE_TIMEOUT_SECS", global = true)]
+    acquire_timeout_secs: Option<u64>,
+
+    /// Seconds after which an idle connection is closed
+    #[arg(long, env = "DATABASE_IDLE_TIMEOUT_SECS", global = true)]
+    idle_timeout_secs: Option<u64>,
+
+    /// Milliseconds after which a statement is logged as slow
+    #[arg(long, env = "DATABASE_SLOW_STATEMENT_MS", global = true)]
+    slow_statement_ms: Option<u64>,
+
+    /// Do not apply pending migrations on startup
+    #[arg(long, env = "SKIP_MIGRATIONS", global = true, num_args = 0..=1, default_missing_value = "true")]
+    skip_migrations: Option<bool>,
+
+    /// What deleting a task does
+    #[arg(long, env = "DELETE_MODE", global = true)]
+    delete_mode: Option<DeleteMode>,
+
+    /// What deleting a task does with its subtasks
+    #[arg(long, env = "SUBTASKS_ON_DELETE", global = true)]
+    subtasks_on_delete: Option<SubtasksOnDelete>,
+
+    /// Days a task stays in the trash before `purge` removes it
+    #[arg(long, env = "TRASH_RETENTION_DAYS", global = true)]
+    retention_days: Option<i64>,
+
+    /// Where the files of attachments are stored
+    #[arg(long, env = "ATTACHMENT_STORE", global = true)]
+    attachment_store: Option<AttachmentStore>,
+
+    /// Directory of the `local` attachment store
+    #[arg(long, env = "ATTACHMENTS_DIR", global = true)]
+    attachments_dir: Option<PathBuf>,
+
+    /// Largest attachment accepted, in bytes
+    #[arg(long, env = "MAX_ATTACHMENT_BYTES", global = true)]
+    max_attachment_bytes: Option<u64>,
+
+    /// Bucket of the `s3` attachment store
+    #[arg(long, env = "S3_BUCKET", global = true)]
+    s3_bucket: Option<String>,
+
+    /// Endpoint of an S3-compatible store such as MinIO, instead of AWS
+    #[arg(long, env = "S3_ENDPOINT", global = true)]
+    s3_endpoint: Option<String>,
+
+    /// Region of the S3 bucket
+    #[arg(long, env = "S3_REGION", global = true)]
+    s3_region: Option<String>,
+
+    /// Access key ID for S3
+    #[arg(long, env = "S3_ACCESS_KEY_ID", global = true)]
+    s3_access_key_id: Option<String>,
+
+    /// Secret access key for S3
+    #[arg(
+        long,
+        env = "S3_SECRET_ACCESS_KEY",
+        global = true,
+        hide_env_values = true
+    )]
+    s3_secret_access_key: Option<String>,
+
+    /// Allow a plain `http://` S3 endpoint
+    #[arg(long, env = "S3_ALLOW_HTTP", global = true, num_args = 0..=1, default_missing_value = "true")]
+    s3_allow_http: Option<bool>,
+
+    /// Log level filter, e.g. `info` or `debug`
+    #[arg(long, env = "LOG_LEVEL", global = true)]
+    log_level: Option<String>,
+
+    /// Log output format
+    #[arg(long, env = "LOG_FORMAT", global = true)]
+    log_format: Option<LogFormat>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Postgres,
+    Memory,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentStore {
+    /// Files below `attachments.dir`
+    Local,
+    /// Objects of an S3 bucket, on AWS or an S3-compatible store
+    S3,
+    /// Process memory, lost on restart
+    Memory,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub storage: StorageBackend,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub tasks: TasksConfig,
+    pub attachments: AttachmentsConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub address: String,
+    pub shutdown_delay_secs: u64,
+    pub drain_timeout_secs: u64,
+    pub pool_close_timeout_secs: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub slow_statement_ms: u64,
+    pub skip_migrations: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TasksConfig {
+    pub delete_mode: DeleteMode,
+    pub subtasks_on_delete: SubtasksOnDelete,
+    pub trash_retention_days: i64,
+    /// Only settable in the config file.
+    pub transitions: Transitions,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttachmentsConfig {
+    pub store: AttachmentStore,
+    pub dir: PathBuf,
+    pub max_size_bytes: u64,
+    pub s3: S3Config,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    pub bucket: Option<String>,
+    /// Endpoint of an S3-compatible store; AWS when missing.
+    pub endpoint: Option<String>,
+    pub region: String,
+    /// Credentials, taken from the usual `AWS_*` environment variables when missing.
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub allow_http: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: String,
+    pub format: LogFormat,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            storage: StorageBackend::Postgres,
+            server: ServerConfig::default(),
+            database: DatabaseConfig::default(),
+            tasks: TasksConfig::default(),
+            attachments: AttachmentsConfig::default(),
+            logging: LoggingConfig::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "0.0.0.0:3000".to_owned(),
+            shutdown_delay_secs: 0,
+            drain_timeout_secs: 30,
+            pool_close_timeout_secs: 5,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 16,
+            min_connections: 0,
+            acquire_timeout_secs: 3,
+            idle_timeout_secs: 600,
+            slow_statement_ms: 1000,
+            skip_migrations: false,
+        }
+    }
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            delete_mode: DeleteMode::Hard,
+            subtasks_on_delete: SubtasksOnDelete::Orphan,
+            trash_retention_days: 30,
+            transitions: Transitions::default(),
+        }
+    }
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            store: AttachmentStore::Local,
+            dir: PathBuf::from("data/attachments"),
+            max_size_bytes: DEFAULT_MAX_ATTACHMENT_BYTES,
+            s3: S3Config::default(),
+        }
+    }
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            bucket: None,
+            endpoint: None,
+            region: "us-east-1".to_owned(),
+            access_key_id: None,
+            secret_access_key: None,
+            allow_http: false,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_owned(),
+            format: LogFormat::Pretty,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, if any, and applies the flags and environment variables on top.
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => {
+                let path = PathBuf::from(DEFAULT_CONFIG_FILE);
+                if path.exists() {
+                    Self::from_file(&path)?
+                } else {
+                    Self::default()
+                }
+            }
+        };
+
+        config.apply(cli.overrides.clone());
+
+        Ok(config)
+    }
+
+    fn from_file(path: &PathBuf) -> Result<Self, ConfigError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| ConfigError::Read(path.clone(), err))?;
+
+        toml::from_str(&content).map_err(|err| ConfigError::Parse(path.clone(), err))
+    }
+
+    fn apply(&mut self, overrides: ConfigOverrides) {
+        macro_rules! apply {
+            ($($source:ident => $target:expr),* $(,)?) => {
+                $(if let Some(value) = overrides.$source {
+                    $target = value;
+                })*
+            };
+        }
+
+        apply!(
+            address => self.server.address,
+            shutdown_delay_secs => self.server.shutdown_delay_secs,
+            drain_timeout_secs => self.server.drain_timeout_secs,
+            pool_close_timeout_secs => self.server.pool_close_timeout_secs,
+            storage => self.storage,
+            max_connections => self.database.max_connections,
+            min_connections => self.database.min_connections,
+            acquire_timeout_secs => self.database.acquire_timeout_secs,
+            idle_timeout_secs => self.database.idle_timeout_secs,
+            slow_statement_ms => self.database.slow_statement_ms,
+            skip_migrations => self.database.skip_migrations,
+            delete_mode => self.tasks.delete_mode,
+            subtasks_on_delete => self.tasks.subtasks_on_delete,
+            retention_days => self.tasks.trash_retention_days,
+            attachment_store => self.attachments.store,
+            attachments_dir => self.attachments.dir,
+            max_attachment_bytes => self.attachments.max_size_bytes,
+            s3_region => self.attachments.s3.region,
+            s3_allow_http => self.attachments.s3.allow_http,
+            log_level => self.logging.level,
+            log_format => self.logging.format,
+        );
+
+        macro_rules! apply_optional {
+            ($($source:ident => $target:expr),* $(,)?) => {
+                $(if overrides.$source.is_some() {
+                    $target = overrides.$source;
+                })*
+            };
+        }
+
+        apply_optional!(
+            database_url => self.database.url,
+            s3_bucket => self.attachments.s3.bucket,
+            s3_endpoint => self.attachments.s3.endpoint,
+            s3_access_key_id => self.attachments.s3.access_key_id,
+            s3_secret_access_key => self.attachments.s3.secret_access_key,
+        );
+    }
+
+    /// The configuration as TOML, with the database password and S3 secret masked.
+    pub fn to_redacted_toml(&self) -> String {
+        let mut printable = toml::Value::try_from(self).expect("The config is always valid TOML");
+
+        if let Some(url) = self.database.url.as_deref() {
+            printable["database"]["url"] = toml::Value::String(redact_password(url));
+        }
+
+        if self.attachments.s3.secret_access_key.is_some() {
+            printable["attachments"]["s3"]["secret_access_key"] =
+                toml::Value::String("***".to_owned());
+        }
+
+        toml::to_string_pretty(&printable).expect("The config is always valid TOML")
+    }
+}
+
+impl ServerConfig {
+    pub fn drain_options(&self) -> DrainOptions {
+        DrainOptions {
+            delay: Duration::from_secs(self.shutdown_delay_secs),
+            timeout: Duration::from_secs(self.drain_timeout_secs),
+        }
+    }
+
+    /// How long the pool gets to close once the server has stopped. Requests dropped at
+    /// the drain timeout may still hold connections, which `close` would wait for forever.
+    pub fn pool_close_timeout(&self) -> Duration {
+        Duration::from_secs(self.pool_close_timeout_secs)
+    }
+}
+
+impl DatabaseConfig {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout_secs)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+
+    pub fn slow_statement_threshold(&self) -> Duration {
+        Duration::from_millis(self.slow_statement_ms)
+    }
+}
+
+fn redact_password(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_owned();
+    };
+
+    let Some((credentials, host)) = rest.rsplit_once('@') else {
+        return url.to_owned();
+    };
+
+    match credentials.split_once(':') {
+        Some((user, _password)) => format!("{scheme}://{user}:***@{host}"),
+        None => url.to_owned(),
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "Could not read {}: {err}", path.display()),
+            ConfigError::Parse(path, err) => {
+                write!(f, "Invalid config in {}: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,59 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::TaskRow;
+
+/// Open live tasks and the dependencies between them, as read by
+/// [`crate::repository::TaskRepository::open_task_graph`].
+pub struct TaskGraph {
+    pub tasks: Vec<TaskRow>,
+    /// `(task_id, blocker_task_id)` pairs.
+    pub dependencies: Vec<(i32, i32)>,
+}
+
+impl TaskGraph {
+    /// The tasks in an order where every task comes after the tasks blocking it, picking
+    /// the lowest `task_id` among the tasks ready at each step.
+    pub fn execution_order(self) -> Vec<TaskRow> {
+        let mut tasks: BTreeMap<i32, TaskRow> = self
+            .tasks
+            .into_iter()
+            .map(|task| (task.task_id, task))
+            .collect();
+
+        let mut open_blockers: BTreeMap<i32, usize> = tasks.keys().map(|&id| (id, 0)).collect();
+        let mut dependents: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+
+        for (task_id, blocker_task_id) in self.dependencies {
+            if tasks.contains_key(&task_id) && tasks.contains_key(&blocker_task_id) {
+                *open_blockers.get_mut(&task_id).unwrap() += 1;
+                dependents.entry(blocker_task_id).or_default().push(task_id);
+            }
+        }
+
+        let mut ready: BTreeSet<i32> = open_blockers
+            .iter()
+            .filter(|&(_, &count)| count == 0)
+            .map(|(&task_id, _)| task_id)
+            .collect();
+        let mut order = Vec::with_capacity(tasks.len());
+
+        while let Some(task_id) = ready.pop_first() {
+            order.push(tasks.remove(&task_id).unwrap());
+
+            for dependent_id in dependents.remove(&task_id).unwrap_or_default() {
+                let count = open_blockers.get_mut(&dependent_id).unwrap();
+                *count -= 1;
+
+                if *count == 0 {
+                    ready.insert(dependent_id);
+                }
+            }
+        }
+
+        // Dependencies are kept acyclic, but should a cycle slip through, its tasks still
+        // show up rather than silently disappear.
+        order.extend(tasks.into_values());
+
+        order
+    }
+}
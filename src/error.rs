@@ -0,0 +1,291 @@
+use std::io;
+
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+use crate::{
+    models::TaskStatus,
+    repository::{ACYCLIC_PARENT_CONSTRAINT, PARENT_TASK_CONSTRAINT},
+};
+
+/// Every failure a handler can report, rendered as an RFC 7807 `application/problem+json` body.
+#[derive(Debug)]
+pub enum AppError {
+    TaskNotFound(i32),
+    TagNotFound(i32),
+    CommentNotFound(i32),
+    AttachmentNotFound(i32),
+    /// Carries the limit, in bytes.
+    AttachmentTooLarge(u64),
+    ParentNotFound,
+    SubtaskCycle,
+    ParentInTrash {
+        task_id: i32,
+        parent_task_id: i32,
+    },
+    DependencyCycle {
+        task_id: i32,
+        blocker_task_id: i32,
+    },
+    PreconditionFailed(i32),
+    InvalidQuery(String),
+    InvalidPath(String),
+    MalformedBody(String),
+    InvalidBody(String),
+    Validation(ValidationErrors),
+    UnsupportedMediaType(String),
+    Conflict(String),
+    IllegalTransition {
+        task_id: i32,
+        from: TaskStatus,
+        to: TaskStatus,
+    },
+    ConstraintViolation(String),
+    DatabaseUnavailable,
+    Database(sqlx::Error),
+    BlobStore(io::Error),
+    /// A bug on the server's side, such as a handler that does not fit its route.
+    Internal(String),
+}
+
+/// Body of every error response, following RFC 7807.
+#[derive(Serialize, ToSchema)]
+pub struct Problem {
+    /// Always `about:blank`; `code` identifies the problem instead.
+    #[serde(rename = "type")]
+    #[schema(example = "about:blank")]
+    pub problem_type: &'static str,
+    #[schema(example = "Not Found")]
+    pub title: &'static str,
+    #[schema(example = 404)]
+    pub status: u16,
+    #[schema(example = "Task 1 not found")]
+    pub detail: String,
+    #[schema(example = "task_not_found")]
+    pub code: &'static str,
+    /// Always `false`.
+    pub success: bool,
+    /// Every failing field, only present when `code` is `validation_failed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FieldError {
+    #[schema(example = "name")]
+    pub field: String,
+    #[schema(example = "length")]
+    pub code: String,
+    #[schema(example = "must be between 1 and 200 characters long")]
+    pub message: String,
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::TaskNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::TagNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::CommentNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::AttachmentNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::AttachmentTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::ParentNotFound => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::SubtaskCycle => StatusCode::CONFLICT,
+            AppError::ParentInTrash { .. } => StatusCode::CONFLICT,
+            AppError::DependencyCycle { .. } => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidPath(_) => StatusCode::BAD_REQUEST,
+            AppError::MalformedBody(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidBody(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::IllegalTransition { .. } => StatusCode::CONFLICT,
+            AppError::ConstraintViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::DatabaseUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::BlobStore(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable, machine-readable identifier of the problem, safe for clients to match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::TaskNotFound(_) => "task_not_found",
+            AppError::TagNotFound(_) => "tag_not_found",
+            AppError::CommentNotFound(_) => "comment_not_found",
+            AppError::AttachmentNotFound(_) => "attachment_not_found",
+            AppError::AttachmentTooLarge(_) => "attachment_too_large",
+            AppError::ParentNotFound => "parent_not_found",
+            AppError::SubtaskCycle => "subtask_cycle",
+            AppError::ParentInTrash { .. } => "parent_in_trash",
+            AppError::DependencyCycle { .. } => "dependency_cycle",
+            AppError::PreconditionFailed(_) => "precondition_failed",
+            AppError::InvalidQuery(_) => "invalid_query",
+            AppError::InvalidPath(_) => "invalid_path",
+            AppError::MalformedBody(_) => "malformed_body",
+            AppError::InvalidBody(_) => "invalid_body",
+            AppError::Validation(_) => "validation_failed",
+            AppError::UnsupportedMediaType(_) => "unsupported_media_type",
+            AppError::Conflict(_) => "conflict",
+            AppError::IllegalTransition { .. } => "illegal_transition",
+            AppError::ConstraintViolation(_) => "constraint_violation",
+            AppError::DatabaseUnavailable => "database_unavailable",
+            AppError::Database(_) => "internal_error",
+            AppError::BlobStore(_) => "storage_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Every failing field of a validation error, sorted by field.
+    fn field_errors(&self) -> Option<Vec<FieldError>> {
+        let AppError::Validation(errors) = self else {
+            return None;
+        };
+
+        let mut field_errors: Vec<FieldError> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| FieldError {
+                    field: field.to_string(),
+                    code: error.code.to_string(),
+                    message: error.message.as_deref().unwrap_or("is invalid").to_owned(),
+                })
+            })
+            .collect();
+
+        field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+        Some(field_errors)
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            AppError::TaskNotFound(task_id) => format!("Task {task_id} not found"),
+            AppError::TagNotFound(tag_id) => format!("Tag {tag_id} not found"),
+            AppError::CommentNotFound(comment_id) => format!("Comment {comment_id} not found"),
+            AppError::AttachmentNotFound(attachment_id) => {
+                format!("Attachment {attachment_id} not found")
+            }
+            AppError::AttachmentTooLarge(max_size) => {
+                format!("Attachments cannot be larger than {max_size} bytes")
+            }
+            AppError::ParentNotFound => {
+                "The parent task does not exist or is in the trash".to_owned()
+            }
+            AppError::SubtaskCycle => {
+                "A task cannot become a subtask of itself or of one of its subtasks".to_owned()
+            }
+            AppError::ParentInTrash {
+                task_id,
+                parent_task_id,
+            } => format!(
+                "Task {task_id} is a subtask of task {parent_task_id}, which is in the trash; restore that one instead"
+            ),
+            AppError::DependencyCycle {
+                task_id,
+                blocker_task_id,
+            } if task_id == blocker_task_id => {
+                format!("Task {task_id} cannot be blocked by itself")
+            }
+            AppError::DependencyCycle {
+                task_id,
+                blocker_task_id,
+            } => format!(
+                "Task {task_id} cannot be blocked by task {blocker_task_id}, which it already blocks"
+            ),
+            AppError::PreconditionFailed(task_id) => {
+                format!("Task {task_id} does not match the If-Match entity tag")
+            }
+            AppError::InvalidQuery(message)
+            | AppError::InvalidPath(message)
+            | AppError::MalformedBody(message)
+            | AppError::InvalidBody(message)
+            | AppError::UnsupportedMediaType(message)
+            | AppError::Conflict(message)
+            | AppError::ConstraintViolation(message) => message.clone(),
+            AppError::IllegalTransition { task_id, from, to } => format!(
+                "Task {task_id} cannot go from {} to {}",
+                from.as_str(),
+                to.as_str()
+            ),
+            AppError::Validation(_) => "One or more fields are invalid".to_owned(),
+            AppError::DatabaseUnavailable => {
+                "The database is not accepting connections right now".to_owned()
+            }
+            AppError::Database(_) => "An unexpected database error occurred".to_owned(),
+            AppError::BlobStore(_) => "The attachment store could not be reached".to_owned(),
+            AppError::Internal(_) => "An unexpected error occurred".to_owned(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match &err {
+            sqlx::Error::Database(db_err)
+                if db_err.constraint() == Some(PARENT_TASK_CONSTRAINT) =>
+            {
+                AppError::ParentNotFound
+            }
+            sqlx::Error::Database(db_err)
+                if db_err.constraint() == Some(ACYCLIC_PARENT_CONSTRAINT) =>
+            {
+                AppError::SubtaskCycle
+            }
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                AppError::Conflict(format!(
+                    "Violates unique constraint {}",
+                    db_err.constraint().unwrap_or("unknown")
+                ))
+            }
+            sqlx::Error::Database(db_err) if db_err.is_check_violation() => {
+                AppError::ConstraintViolation(format!(
+                    "Violates check constraint {}",
+                    db_err.constraint().unwrap_or("unknown")
+                ))
+            }
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => AppError::DatabaseUnavailable,
+            _ => AppError::Database(err),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match &self {
+            AppError::Database(err) => tracing::error!(error = %err, "Unexpected database error"),
+            AppError::DatabaseUnavailable => tracing::warn!("No database connection available"),
+            AppError::BlobStore(err) => tracing::error!(error = %err, "Attachment store error"),
+            AppError::Internal(message) => tracing::error!(error = %message, "Internal error"),
+            _ => {}
+        }
+
+        let status_code = self.status_code();
+
+        let body = Problem {
+            problem_type: "about:blank",
+            title: status_code.canonical_reason().unwrap_or("Error"),
+            status: status_code.as_u16(),
+            detail: self.detail(),
+            code: self.code(),
+            success: false,
+            errors: self.field_errors(),
+        };
+
+        (
+            status_code,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(body),
+        )
+            .into_response()
+    }
+}
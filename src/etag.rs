@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, HeaderName, header, request::Parts},
+};
+
+use crate::{error::AppError, models::TaskRow};
+
+/// Strong entity tag of a task, derived from its version.
+pub fn task_etag(task: &TaskRow) -> String {
+    format!("\"{}\"", task.version)
+}
+
+/// Entity tags listed by an `If-Match` or `If-None-Match` header.
+pub enum EntityTags {
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl EntityTags {
+    fn from_headers(headers: &HeaderMap, name: HeaderName, weak_allowed: bool) -> Option<Self> {
+        let value = headers.get(name)?.to_str().unwrap_or_default().trim();
+
+        if value == "*" {
+            return Some(EntityTags::Any);
+        }
+
+        // Entity tags that are not ours simply never match.
+        let versions = value
+            .split(',')
+            .map(str::trim)
+            .filter_map(|tag| match tag.strip_prefix("W/") {
+                Some(weak_tag) if weak_allowed => Some(weak_tag),
+                Some(_) => None,
+                None => Some(tag),
+            })
+            .filter_map(|tag| tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok())
+            .collect();
+
+        Some(EntityTags::Versions(versions))
+    }
+
+    pub fn matches(&self, version: i32) -> bool {
+        match self {
+            EntityTags::Any => true,
+            EntityTags::Versions(versions) => versions.contains(&version),
+        }
+    }
+}
+
+/// `If-Match` precondition of a write, compared strongly against the task's entity tag.
+pub struct IfMatch(pub Option<EntityTags>);
+
+impl IfMatch {
+    /// Versions the stored task must have for the write to go through, if restricted.
+    pub fn expected_versions(&self) -> Option<&[i32]> {
+        match &self.0 {
+            Some(EntityTags::Versions(versions)) => Some(versions),
+            Some(EntityTags::Any) | None => None,
+        }
+    }
+
+    pub fn check(&self, task: &TaskRow) -> Result<(), AppError> {
+        match &self.0 {
+            Some(tags) if !tags.matches(task.version) => {
+                Err(AppError::PreconditionFailed(task.task_id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `If-None-Match` condition of a read, compared weakly against the task's entity tag.
+pub struct IfNoneMatch(pub Option<EntityTags>);
+
+impl IfNoneMatch {
+    pub fn matches(&self, task: &TaskRow) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|tags| tags.matches(task.version))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfMatch(EntityTags::from_headers(
+            &parts.headers,
+            header::IF_MATCH,
+            false,
+        )))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfNoneMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfNoneMatch(EntityTags::from_headers(
+            &parts.headers,
+            header::IF_NONE_MATCH,
+            true,
+        )))
+    }
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use axum::{
+    Json,
+    extract::{
+        FromRequest, FromRequestParts, Multipart, Request,
+        rejection::{JsonRejection, PathRejection},
+    },
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use crate::error::AppError;
+
+/// JSON body that is rejected with a problem response when it is malformed,
+/// has the wrong shape, or breaks any of its validation rules.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                JsonRejection::JsonDataError(err) => AppError::InvalidBody(err.body_text()),
+                JsonRejection::MissingJsonContentType(err) => {
+                    AppError::UnsupportedMediaType(err.body_text())
+                }
+                other => AppError::MalformedBody(other.body_text()),
+            })?;
+
+        value.validate().map_err(AppError::Validation)?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// `multipart/form-data` body, rejected with a problem response when the request is not one.
+pub struct MultipartForm(pub Multipart);
+
+#[async_trait]
+impl<S> FromRequest<S> for MultipartForm
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(|rejection| AppError::UnsupportedMediaType(rejection.body_text()))?;
+
+        Ok(MultipartForm(multipart))
+    }
+}
+
+/// Query string that is rejected with a problem response when it cannot be deserialized.
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) =
+            axum::extract::Query::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|rejection| AppError::InvalidQuery(rejection.body_text()))?;
+
+        Ok(Query(value))
+    }
+}
+
+/// Path parameters that are rejected with a problem response when they cannot be
+/// deserialized, e.g. a `task_id` that is not a number. Any other rejection means the
+/// handler does not fit its route, which is a bug of ours rather than of the client.
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| match rejection {
+                PathRejection::FailedToDeserializePathParams(err) => {
+                    AppError::InvalidPath(err.body_text())
+                }
+                other => AppError::Internal(other.body_text()),
+            })?;
+
+        Ok(Path(value))
+    }
+}
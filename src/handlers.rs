@@ -0,0 +1,474 @@
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    error::{AppError, Problem},
+    etag::{IfMatch, IfNoneMatch, task_etag},
+    extract::{Path, Query, ValidatedJson},
+    models::{
+        CreateTaskReq, CreateTaskRow, ReplaceTaskReq, SearchHit, TaskRow, TaskStatus, UpdateTaskReq,
+    },
+    openapi::{EmptyResponse, JsonPatchOperation},
+    patch::{TaskPatch, apply_json_patch},
+    query::{
+        Cursor, ListTasksParams, SearchCursor, SearchTasksParams, TaskListQuery, TaskSearchQuery,
+    },
+    repository::RestoreOutcome,
+    response::{ApiResponse, ApiResult, PageMeta},
+    state::{AppState, DeleteMode},
+};
+
+pub mod attachments;
+pub mod comments;
+pub mod dependencies;
+pub mod subtasks;
+pub mod tags;
+
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    tag = "tasks",
+    params(ListTasksParams),
+    responses(
+        (status = 200, description = "A page of live tasks", body = ApiResponse<Vec<TaskRow>>),
+        (status = 400, description = "Invalid query parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_tasks(
+    State(state): State<AppState>,
+    Query(params): Query<ListTasksParams>,
+) -> ApiResult<Vec<TaskRow>> {
+    let query = TaskListQuery::from_params(params).map_err(AppError::InvalidQuery)?;
+
+    list_tasks_page(&state, &query).await
+}
+
+async fn list_tasks_page(state: &AppState, query: &TaskListQuery) -> ApiResult<Vec<TaskRow>> {
+    let page = state.repository.list(query).await?;
+
+    let next_cursor = page
+        .tasks
+        .last()
+        .filter(|_| page.has_more)
+        .map(|task| Cursor::after(task, &query.sort).encode());
+
+    let meta = PageMeta {
+        total: page.total,
+        limit: query.limit,
+        offset: query.offset,
+        next_cursor,
+    };
+
+    Ok(ApiResponse::ok(page.tasks).with_meta(meta))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/search",
+    tag = "tasks",
+    params(SearchTasksParams),
+    responses(
+        (status = 200, description = "A page of the live tasks whose name matches, the most relevant first", body = ApiResponse<Vec<SearchHit>>),
+        (status = 400, description = "Invalid query parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn search_tasks(
+    State(state): State<AppState>,
+    Query(params): Query<SearchTasksParams>,
+) -> ApiResult<Vec<SearchHit>> {
+    let query = TaskSearchQuery::from_params(params).map_err(AppError::InvalidQuery)?;
+
+    let page = state.repository.search(&query).await?;
+
+    let next_cursor = page
+        .hits
+        .last()
+        .filter(|_| page.has_more)
+        .map(|hit| SearchCursor::after(&query, hit).encode());
+
+    let meta = PageMeta {
+        total: page.total,
+        limit: query.limit,
+        offset: query.offset,
+        next_cursor,
+    };
+
+    Ok(ApiResponse::ok(page.hits).with_meta(meta))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}",
+    tag = "tasks",
+    params(
+        ("task_id" = i32, Path),
+        ("If-None-Match" = Option<String>, Header, description = "Answer 304 when the task still has one of these entity tags"),
+    ),
+    responses(
+        (status = 200, description = "The task", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 304, description = "The task matches `If-None-Match`", headers(("ETag" = String))),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    if_none_match: IfNoneMatch,
+) -> Result<Response, AppError> {
+    let task = state
+        .repository
+        .find_by_id(task_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    let etag = task_etag(&task);
+
+    if if_none_match.matches(&task) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    Ok(ApiResponse::ok(task).with_etag(&etag).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "tasks",
+    request_body = CreateTaskReq,
+    responses(
+        (status = 200, description = "The task was created", body = ApiResponse<CreateTaskRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The task is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn create_task(
+    State(state): State<AppState>,
+    ValidatedJson(task): ValidatedJson<CreateTaskReq>,
+) -> ApiResult<CreateTaskRow> {
+    let row = state.repository.create(task).await?;
+
+    Ok(ApiResponse::ok(row))
+}
+
+/// How many times a read-modify-write, like a JSON Patch or a status transition, is
+/// retried when the task changes between read and write.
+const WRITE_ATTEMPTS: usize = 3;
+
+#[utoipa::path(
+    patch,
+    path = "/tasks/{task_id}",
+    tag = "tasks",
+    params(
+        ("task_id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "Only update the task while it has one of these entity tags"),
+    ),
+    request_body(
+        description = "A JSON Merge Patch, also accepted as `application/json`, or a JSON Patch",
+        content(
+            (UpdateTaskReq = "application/merge-patch+json"),
+            (Vec<JsonPatchOperation> = "application/json-patch+json"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "The updated task", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A JSON Patch `test` failed or the task kept changing", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The task does not match `If-Match`", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The patched task is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn update_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    if_match: IfMatch,
+    patch: TaskPatch,
+) -> ApiResult<TaskRow> {
+    let patch = match patch {
+        TaskPatch::Merge(patch) => {
+            let task = state
+                .repository
+                .update(task_id, patch.into_changes()?, if_match.expected_versions())
+                .await?;
+
+            return match task {
+                Some(task) => {
+                    let etag = task_etag(&task);
+                    Ok(ApiResponse::ok(task).with_etag(&etag))
+                }
+                None => Err(write_failure(&state, task_id).await),
+            };
+        }
+        TaskPatch::Json(patch) => patch,
+    };
+
+    for _ in 0..WRITE_ATTEMPTS {
+        let original_task = state
+            .repository
+            .find_by_id(task_id)
+            .await?
+            .ok_or(AppError::TaskNotFound(task_id))?;
+
+        if_match.check(&original_task)?;
+
+        let task = apply_json_patch(&patch, &original_task)?;
+
+        let updated_task = state
+            .repository
+            .update(task_id, task.into(), Some(&[original_task.version]))
+            .await?;
+
+        if let Some(updated_task) = updated_task {
+            let etag = task_etag(&updated_task);
+            return Ok(ApiResponse::ok(updated_task).with_etag(&etag));
+        }
+    }
+
+    Err(AppError::Conflict(format!(
+        "Task {task_id} kept changing while the patch was applied"
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}",
+    tag = "tasks",
+    params(
+        ("task_id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "Only replace the task while it has one of these entity tags"),
+    ),
+    request_body = ReplaceTaskReq,
+    responses(
+        (status = 200, description = "The replaced task", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The task does not match `If-Match`", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The task is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn replace_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    if_match: IfMatch,
+    ValidatedJson(task): ValidatedJson<ReplaceTaskReq>,
+) -> ApiResult<TaskRow> {
+    let task = state
+        .repository
+        .update(task_id, task.into(), if_match.expected_versions())
+        .await?;
+
+    match task {
+        Some(task) => {
+            let etag = task_etag(&task);
+            Ok(ApiResponse::ok(task).with_etag(&etag))
+        }
+        None => Err(write_failure(&state, task_id).await),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}",
+    tag = "tasks",
+    description = "Removes the task for good or moves it to the trash, depending on the configured delete mode. \
+        Its subtasks are either deleted along with it or turned into top-level tasks, depending on the configured subtask mode.",
+    params(
+        ("task_id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "Only delete the task while it has one of these entity tags"),
+    ),
+    responses(
+        (status = 200, description = "The task was deleted", body = EmptyResponse),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The task does not match `If-Match`", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    if_match: IfMatch,
+) -> ApiResult<()> {
+    let expected_versions = if_match.expected_versions();
+
+    let subtasks = state.subtasks_on_delete;
+
+    let deleted = match state.delete_mode {
+        DeleteMode::Hard => {
+            state
+                .repository
+                .delete(task_id, expected_versions, subtasks)
+                .await?
+        }
+        DeleteMode::Soft => {
+            state
+                .repository
+                .soft_delete(task_id, expected_versions, subtasks)
+                .await?
+        }
+    };
+
+    if !deleted {
+        return Err(write_failure(&state, task_id).await);
+    }
+
+    Ok(ApiResponse::empty())
+}
+
+/// Tells apart a write that found no task from one whose `If-Match` did not hold.
+async fn write_failure(state: &AppState, task_id: i32) -> AppError {
+    match state.repository.find_by_id(task_id).await {
+        Ok(Some(_)) => AppError::PreconditionFailed(task_id),
+        Ok(None) => AppError::TaskNotFound(task_id),
+        Err(err) => err.into(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/trash",
+    tag = "tasks",
+    params(ListTasksParams),
+    responses(
+        (status = 200, description = "A page of trashed tasks", body = ApiResponse<Vec<TaskRow>>),
+        (status = 400, description = "Invalid query parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_trashed_tasks(
+    State(state): State<AppState>,
+    Query(params): Query<ListTasksParams>,
+) -> ApiResult<Vec<TaskRow>> {
+    let mut query = TaskListQuery::from_params(params).map_err(AppError::InvalidQuery)?;
+    query.filter.deleted = true;
+
+    list_tasks_page(&state, &query).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/restore",
+    tag = "tasks",
+    params(("task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task and the subtasks trashed with it are live again", body = EmptyResponse),
+        (status = 404, description = "No such task in the trash", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The task's parent is in the trash", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn restore_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+) -> ApiResult<()> {
+    match state.repository.restore(task_id).await? {
+        RestoreOutcome::Restored => Ok(ApiResponse::empty()),
+        RestoreOutcome::NotInTrash => Err(AppError::TaskNotFound(task_id)),
+        RestoreOutcome::ParentInTrash(parent_task_id) => Err(AppError::ParentInTrash {
+            task_id,
+            parent_task_id,
+        }),
+    }
+}
+
+/// Defines a `POST /tasks/{task_id}/<action>` handler moving the task to `$status`.
+macro_rules! transition_handler {
+    ($name:ident, $path:literal, $status:expr, $description:literal) => {
+        #[utoipa::path(
+            post,
+            path = $path,
+            tag = "tasks",
+            description = $description,
+            params(
+                ("task_id" = i32, Path),
+                ("If-Match" = Option<String>, Header, description = "Only change the task while it has one of these entity tags"),
+            ),
+            responses(
+                (status = 200, description = "The updated task", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+                (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+                (status = 409, description = "The transition is not allowed from the current status", body = Problem, content_type = "application/problem+json"),
+                (status = 412, description = "The task does not match `If-Match`", body = Problem, content_type = "application/problem+json"),
+            ),
+        )]
+        pub async fn $name(
+            State(state): State<AppState>,
+            Path(task_id): Path<i32>,
+            if_match: IfMatch,
+        ) -> ApiResult<TaskRow> {
+            transition_task(&state, task_id, &if_match, $status).await
+        }
+    };
+}
+
+transition_handler!(
+    start_task,
+    "/tasks/{task_id}/start",
+    TaskStatus::InProgress,
+    "Moves the task to `in_progress`."
+);
+transition_handler!(
+    block_task,
+    "/tasks/{task_id}/block",
+    TaskStatus::Blocked,
+    "Moves the task to `blocked`."
+);
+transition_handler!(
+    complete_task,
+    "/tasks/{task_id}/complete",
+    TaskStatus::Done,
+    "Moves the task to `done`, stamping `completed_at`."
+);
+transition_handler!(
+    cancel_task,
+    "/tasks/{task_id}/cancel",
+    TaskStatus::Cancelled,
+    "Moves the task to `cancelled`."
+);
+transition_handler!(
+    reopen_task,
+    "/tasks/{task_id}/reopen",
+    TaskStatus::Todo,
+    "Moves the task back to `todo`."
+);
+
+/// Moves a task to `status` when the configured transitions allow it from its current one.
+async fn transition_task(
+    state: &AppState,
+    task_id: i32,
+    if_match: &IfMatch,
+    status: TaskStatus,
+) -> ApiResult<TaskRow> {
+    for _ in 0..WRITE_ATTEMPTS {
+        let task = state
+            .repository
+            .find_by_id(task_id)
+            .await?
+            .ok_or(AppError::TaskNotFound(task_id))?;
+
+        if_match.check(&task)?;
+
+        if !state.transitions.allows(task.status, status) {
+            return Err(AppError::IllegalTransition {
+                task_id,
+                from: task.status,
+                to: status,
+            });
+        }
+
+        let updated_task = state
+            .repository
+            .set_status(task_id, status, Some(&[task.version]))
+            .await?;
+
+        if let Some(updated_task) = updated_task {
+            let etag = task_etag(&updated_task);
+            return Ok(ApiResponse::ok(updated_task).with_etag(&etag));
+        }
+    }
+
+    Err(AppError::Conflict(format!(
+        "Task {task_id} kept changing while its status was updated"
+    )))
+}
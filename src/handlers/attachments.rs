@@ -0,0 +1,233 @@
+use axum::{
+    body::Body,
+    extract::{State, multipart::MultipartError},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    attachments::{self, clean_file_name, content_disposition, sniff_content_type, stream_field},
+    error::{AppError, Problem},
+    extract::{MultipartForm, Path},
+    models::{AttachmentRow, NewAttachment},
+    openapi::{EmptyResponse, FileContents, UploadAttachmentForm},
+    response::{ApiResponse, ApiResult},
+    state::AppState,
+};
+
+/// Form field carrying the uploaded file.
+const FILE_FIELD: &str = "file";
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/attachments",
+    tag = "tasks",
+    params(("task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task's attachments, oldest first", body = ApiResponse<Vec<AttachmentRow>>),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_attachments(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+) -> ApiResult<Vec<AttachmentRow>> {
+    if state.repository.find_by_id(task_id).await?.is_none() {
+        return Err(AppError::TaskNotFound(task_id));
+    }
+
+    let attachments = state.repository.list_attachments(task_id).await?;
+
+    Ok(ApiResponse::ok(attachments))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/attachments",
+    tag = "tasks",
+    description = "Uploads the `file` field of the form as a new attachment of the task. Its \
+        type is detected from its contents. Attachments do not change the task's version.",
+    params(("task_id" = i32, Path)),
+    request_body(content = UploadAttachmentForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The attachment was stored", body = ApiResponse<AttachmentRow>),
+        (status = 400, description = "The body is not a valid form", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "The file is larger than allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `multipart/form-data`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The form has no `file` field, or the file is empty", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    MultipartForm(mut form): MultipartForm,
+) -> ApiResult<AttachmentRow> {
+    if state.repository.find_by_id(task_id).await?.is_none() {
+        return Err(AppError::TaskNotFound(task_id));
+    }
+
+    let max_size = state.max_attachment_bytes;
+
+    let field = loop {
+        match form
+            .next_field()
+            .await
+            .map_err(|err| body_error(err, max_size))?
+        {
+            Some(field) if field.name() == Some(FILE_FIELD) => break field,
+            Some(_) => continue,
+            None => {
+                return Err(AppError::InvalidBody(format!(
+                    "The form has no `{FILE_FIELD}` field"
+                )));
+            }
+        }
+    };
+
+    let file_name = clean_file_name(field.file_name());
+    let storage_key = attachments::storage_key(task_id);
+
+    let (contents, progress) = stream_field(field, max_size);
+    let stored = state.blob_store.put(&storage_key, contents).await;
+    let progress = std::mem::take(&mut *progress.lock().unwrap());
+
+    if let Err(err) = stored {
+        return Err(match progress.body_error {
+            Some(body_err) => body_error(body_err, max_size),
+            None if progress.size > max_size => AppError::AttachmentTooLarge(max_size),
+            None => AppError::BlobStore(err),
+        });
+    }
+
+    if progress.size == 0 {
+        discard_blob(&state, &storage_key).await;
+        return Err(AppError::InvalidBody("The file is empty".to_owned()));
+    }
+
+    let attachment = NewAttachment {
+        task_id,
+        file_name,
+        content_type: sniff_content_type(&progress.head),
+        size: progress.size as i64,
+        storage_key: storage_key.clone(),
+    };
+
+    // The task may have been deleted while the file was uploading.
+    match state.repository.create_attachment(attachment).await {
+        Ok(Some(attachment)) => Ok(ApiResponse::ok(attachment)),
+        Ok(None) => {
+            discard_blob(&state, &storage_key).await;
+            Err(AppError::TaskNotFound(task_id))
+        }
+        Err(err) => {
+            discard_blob(&state, &storage_key).await;
+            Err(err.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/attachments/{attachment_id}",
+    tag = "tasks",
+    description = "Downloads the file of the attachment, with its detected type.",
+    params(("task_id" = i32, Path), ("attachment_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The file", body = FileContents, content_type = "application/octet-stream",
+            headers(("Content-Disposition" = String, description = "`attachment`, with the file name"))),
+        (status = 404, description = "No such live task or no such attachment of it", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    Path((task_id, attachment_id)): Path<(i32, i32)>,
+) -> Result<Response, AppError> {
+    let Some(attachment) = state
+        .repository
+        .find_attachment(task_id, attachment_id)
+        .await?
+    else {
+        return Err(attachment_not_found(&state, task_id, attachment_id).await);
+    };
+
+    let contents = state
+        .blob_store
+        .get(&attachment.storage_key)
+        .await
+        .map_err(AppError::BlobStore)?
+        .ok_or_else(|| {
+            AppError::BlobStore(std::io::Error::other(format!(
+                "The file of attachment {attachment_id} is missing"
+            )))
+        })?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, attachment.content_type),
+            (header::CONTENT_LENGTH, attachment.size.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                content_disposition(&attachment.file_name),
+            ),
+            // Browsers must not second-guess the detected type.
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_owned()),
+        ],
+        Body::from_stream(contents),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/attachments/{attachment_id}",
+    tag = "tasks",
+    params(("task_id" = i32, Path), ("attachment_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The attachment and its file were deleted", body = EmptyResponse),
+        (status = 404, description = "No such live task or no such attachment of it", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    Path((task_id, attachment_id)): Path<(i32, i32)>,
+) -> ApiResult<()> {
+    let Some(attachment) = state
+        .repository
+        .delete_attachment(task_id, attachment_id)
+        .await?
+    else {
+        return Err(attachment_not_found(&state, task_id, attachment_id).await);
+    };
+
+    discard_blob(&state, &attachment.storage_key).await;
+
+    Ok(ApiResponse::empty())
+}
+
+/// Tells whether the task or the attachment is the one missing.
+async fn attachment_not_found(state: &AppState, task_id: i32, attachment_id: i32) -> AppError {
+    match state.repository.find_by_id(task_id).await {
+        Ok(Some(_)) => AppError::AttachmentNotFound(attachment_id),
+        Ok(None) => AppError::TaskNotFound(task_id),
+        Err(err) => err.into(),
+    }
+}
+
+/// The problem of a request body that could not be read as a form.
+fn body_error(err: MultipartError, max_size: u64) -> AppError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::AttachmentTooLarge(max_size)
+    } else {
+        AppError::MalformedBody(err.body_text())
+    }
+}
+
+/// Deletes a blob no attachment refers to. A failure is only logged: `purge` sweeps such
+/// blobs later on.
+async fn discard_blob(state: &AppState, storage_key: &str) {
+    if let Err(err) = state.blob_store.delete(storage_key).await {
+        tracing::warn!(error = %err, storage_key, "Could not delete an unused attachment file");
+    }
+}
@@ -0,0 +1,168 @@
+use axum::extract::State;
+
+use crate::{
+    error::{AppError, Problem},
+    extract::{Path, Query, ValidatedJson},
+    models::{CommentRow, CreateCommentReq, UpdateCommentReq},
+    openapi::EmptyResponse,
+    query::{CommentCursor, CommentListQuery, ListCommentsParams},
+    response::{ApiResponse, ApiResult, PageMeta},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/comments",
+    tag = "tasks",
+    description = "Lists the comments of the task, oldest first.",
+    params(("task_id" = i32, Path), ListCommentsParams),
+    responses(
+        (status = 200, description = "A page of the task's comments", body = ApiResponse<Vec<CommentRow>>),
+        (status = 400, description = "Invalid query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_comments(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    Query(params): Query<ListCommentsParams>,
+) -> ApiResult<Vec<CommentRow>> {
+    let query = CommentListQuery::from_params(params).map_err(AppError::InvalidQuery)?;
+
+    if state.repository.find_by_id(task_id).await?.is_none() {
+        return Err(AppError::TaskNotFound(task_id));
+    }
+
+    let page = state.repository.list_comments(task_id, &query).await?;
+
+    let next_cursor = page
+        .comments
+        .last()
+        .filter(|_| page.has_more)
+        .map(|comment| CommentCursor::after(comment).encode());
+
+    let meta = PageMeta {
+        total: page.total,
+        limit: query.limit,
+        offset: query.offset,
+        next_cursor,
+    };
+
+    let comments = page
+        .comments
+        .into_iter()
+        .map(CommentRow::with_html)
+        .collect();
+    Ok(ApiResponse::ok(comments).with_meta(meta))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/comments",
+    tag = "tasks",
+    description = "Adds a comment to the task, bumping the task's version since its `comment_count` changes.",
+    params(("task_id" = i32, Path)),
+    request_body = CreateCommentReq,
+    responses(
+        (status = 200, description = "The comment was created", body = ApiResponse<CommentRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The comment is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn create_comment(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    ValidatedJson(comment): ValidatedJson<CreateCommentReq>,
+) -> ApiResult<CommentRow> {
+    let comment = state
+        .repository
+        .create_comment(task_id, comment)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    Ok(ApiResponse::ok(comment.with_html()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/comments/{comment_id}",
+    tag = "tasks",
+    params(("task_id" = i32, Path), ("comment_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The comment", body = ApiResponse<CommentRow>),
+        (status = 404, description = "No such live task or no such comment on it", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_comment(
+    State(state): State<AppState>,
+    Path((task_id, comment_id)): Path<(i32, i32)>,
+) -> ApiResult<CommentRow> {
+    match state.repository.find_comment(task_id, comment_id).await? {
+        Some(comment) => Ok(ApiResponse::ok(comment.with_html())),
+        None => Err(comment_not_found(&state, task_id, comment_id).await),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/tasks/{task_id}/comments/{comment_id}",
+    tag = "tasks",
+    description = "Replaces the body of the comment; its author cannot change.",
+    params(("task_id" = i32, Path), ("comment_id" = i32, Path)),
+    request_body = UpdateCommentReq,
+    responses(
+        (status = 200, description = "The updated comment", body = ApiResponse<CommentRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task or no such comment on it", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The new body is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn update_comment(
+    State(state): State<AppState>,
+    Path((task_id, comment_id)): Path<(i32, i32)>,
+    ValidatedJson(changes): ValidatedJson<UpdateCommentReq>,
+) -> ApiResult<CommentRow> {
+    let comment = state
+        .repository
+        .update_comment(task_id, comment_id, changes.body)
+        .await?;
+
+    match comment {
+        Some(comment) => Ok(ApiResponse::ok(comment.with_html())),
+        None => Err(comment_not_found(&state, task_id, comment_id).await),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/comments/{comment_id}",
+    tag = "tasks",
+    description = "Deletes the comment, bumping the task's version since its `comment_count` changes.",
+    params(("task_id" = i32, Path), ("comment_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The comment was deleted", body = EmptyResponse),
+        (status = 404, description = "No such live task or no such comment on it", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Path((task_id, comment_id)): Path<(i32, i32)>,
+) -> ApiResult<()> {
+    if !state.repository.delete_comment(task_id, comment_id).await? {
+        return Err(comment_not_found(&state, task_id, comment_id).await);
+    }
+
+    Ok(ApiResponse::empty())
+}
+
+/// Tells whether the task or the comment is the one missing.
+async fn comment_not_found(state: &AppState, task_id: i32, comment_id: i32) -> AppError {
+    match state.repository.find_by_id(task_id).await {
+        Ok(Some(_)) => AppError::CommentNotFound(comment_id),
+        Ok(None) => AppError::TaskNotFound(task_id),
+        Err(err) => err.into(),
+    }
+}
@@ -0,0 +1,119 @@
+use axum::extract::State;
+
+use crate::{
+    error::{AppError, Problem},
+    etag::task_etag,
+    extract::Path,
+    models::TaskRow,
+    repository::ACYCLIC_DEPENDENCY_CONSTRAINT,
+    response::{ApiResponse, ApiResult},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/dependencies",
+    tag = "tasks",
+    params(("task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The live tasks blocking the task, by `task_id`", body = ApiResponse<Vec<TaskRow>>),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_dependencies(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+) -> ApiResult<Vec<TaskRow>> {
+    let blockers = state
+        .repository
+        .blockers(task_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    Ok(ApiResponse::ok(blockers))
+}
+
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/dependencies/{blocker_task_id}",
+    tag = "tasks",
+    description = "Records that the task is blocked by another one; recording it again changes nothing.",
+    params(("task_id" = i32, Path), ("blocker_task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The blocked task", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 404, description = "One of the tasks is not live", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The blocker already depends on the task, directly or not", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn add_dependency(
+    State(state): State<AppState>,
+    Path((task_id, blocker_task_id)): Path<(i32, i32)>,
+) -> ApiResult<TaskRow> {
+    let task = state
+        .repository
+        .add_dependency(task_id, blocker_task_id)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err)
+                if db_err.constraint() == Some(ACYCLIC_DEPENDENCY_CONSTRAINT) =>
+            {
+                AppError::DependencyCycle {
+                    task_id,
+                    blocker_task_id,
+                }
+            }
+            _ => err.into(),
+        })?;
+
+    let Some(task) = task else {
+        // Tell which of the two tasks is missing.
+        return match state.repository.find_by_id(task_id).await? {
+            Some(_) => Err(AppError::TaskNotFound(blocker_task_id)),
+            None => Err(AppError::TaskNotFound(task_id)),
+        };
+    };
+
+    let etag = task_etag(&task);
+    Ok(ApiResponse::ok(task).with_etag(&etag))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/dependencies/{blocker_task_id}",
+    tag = "tasks",
+    description = "Removes a dependency; removing one the task does not have changes nothing.",
+    params(("task_id" = i32, Path), ("blocker_task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task that was blocked", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn remove_dependency(
+    State(state): State<AppState>,
+    Path((task_id, blocker_task_id)): Path<(i32, i32)>,
+) -> ApiResult<TaskRow> {
+    let task = state
+        .repository
+        .remove_dependency(task_id, blocker_task_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    let etag = task_etag(&task);
+    Ok(ApiResponse::ok(task).with_etag(&etag))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/order",
+    tag = "tasks",
+    description = "Lists the live tasks that are neither `done` nor `cancelled` so that every task \
+        comes after the tasks blocking it, breaking ties by `task_id`.",
+    responses(
+        (status = 200, description = "The open tasks in execution order", body = ApiResponse<Vec<TaskRow>>),
+    ),
+)]
+pub async fn get_execution_order(State(state): State<AppState>) -> ApiResult<Vec<TaskRow>> {
+    let graph = state.repository.open_task_graph().await?;
+
+    Ok(ApiResponse::ok(graph.execution_order()))
+}
@@ -0,0 +1,63 @@
+use axum::extract::State;
+
+use super::list_tasks_page;
+use crate::{
+    error::{AppError, Problem},
+    extract::{Path, Query},
+    models::TaskRow,
+    query::{ListTasksParams, TaskListQuery},
+    response::{ApiResponse, ApiResult},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/children",
+    tag = "tasks",
+    description = "Lists the direct subtasks of the task, with the same query parameters as `GET /tasks`.",
+    params(("task_id" = i32, Path), ListTasksParams),
+    responses(
+        (status = 200, description = "A page of the task's live direct subtasks", body = ApiResponse<Vec<TaskRow>>),
+        (status = 400, description = "Invalid query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_children(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+    Query(params): Query<ListTasksParams>,
+) -> ApiResult<Vec<TaskRow>> {
+    let mut query = TaskListQuery::from_params(params).map_err(AppError::InvalidQuery)?;
+    query.filter.parent_task_id = Some(task_id);
+
+    if state.repository.find_by_id(task_id).await?.is_none() {
+        return Err(AppError::TaskNotFound(task_id));
+    }
+
+    list_tasks_page(&state, &query).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/subtree",
+    tag = "tasks",
+    description = "Returns the task followed by its live subtasks at any depth, depth first, \
+        with siblings by `task_id`. Their `parent_task_id` gives the shape of the tree.",
+    params(("task_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task and every subtask below it", body = ApiResponse<Vec<TaskRow>>),
+        (status = 404, description = "No such live task", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_subtree(
+    State(state): State<AppState>,
+    Path(task_id): Path<i32>,
+) -> ApiResult<Vec<TaskRow>> {
+    let tasks = state
+        .repository
+        .subtree(task_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    Ok(ApiResponse::ok(tasks))
+}
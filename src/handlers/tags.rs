@@ -0,0 +1,231 @@
+use axum::extract::State;
+
+use crate::{
+    error::{AppError, Problem},
+    etag::task_etag,
+    extract::{Path, ValidatedJson},
+    models::{CreateTagReq, MergeTagReq, TagRow, TaskRow, UpdateTagReq},
+    openapi::EmptyResponse,
+    response::{ApiResponse, ApiResult},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    responses(
+        (status = 200, description = "Every tag, by name", body = ApiResponse<Vec<TagRow>>),
+    ),
+)]
+pub async fn get_tags(State(state): State<AppState>) -> ApiResult<Vec<TagRow>> {
+    let tags = state.repository.list_tags().await?;
+
+    Ok(ApiResponse::ok(tags))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags",
+    tag = "tags",
+    request_body = CreateTagReq,
+    responses(
+        (status = 200, description = "The tag was created", body = ApiResponse<TagRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Another tag has the same name, ignoring case", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The tag is invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn create_tag(
+    State(state): State<AppState>,
+    ValidatedJson(tag): ValidatedJson<CreateTagReq>,
+) -> ApiResult<TagRow> {
+    let name = tag.name.clone();
+    let tag = state
+        .repository
+        .create_tag(tag)
+        .await
+        .map_err(|err| name_conflict(err, &name))?;
+
+    Ok(ApiResponse::ok(tag))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags/{tag_id}",
+    tag = "tags",
+    params(("tag_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The tag", body = ApiResponse<TagRow>),
+        (status = 404, description = "No such tag", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_tag(State(state): State<AppState>, Path(tag_id): Path<i32>) -> ApiResult<TagRow> {
+    let tag = state
+        .repository
+        .find_tag(tag_id)
+        .await?
+        .ok_or(AppError::TagNotFound(tag_id))?;
+
+    Ok(ApiResponse::ok(tag))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/tags/{tag_id}",
+    tag = "tags",
+    description = "Renames and/or recolors the tag, which counts as a change of every task it is attached to.",
+    params(("tag_id" = i32, Path)),
+    request_body = UpdateTagReq,
+    responses(
+        (status = 200, description = "The updated tag", body = ApiResponse<TagRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such tag", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Another tag has the new name, ignoring case", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The changes are invalid", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn update_tag(
+    State(state): State<AppState>,
+    Path(tag_id): Path<i32>,
+    ValidatedJson(changes): ValidatedJson<UpdateTagReq>,
+) -> ApiResult<TagRow> {
+    let name = changes.name.clone().unwrap_or_default();
+    let tag = state
+        .repository
+        .update_tag(tag_id, changes)
+        .await
+        .map_err(|err| name_conflict(err, &name))?
+        .ok_or(AppError::TagNotFound(tag_id))?;
+
+    Ok(ApiResponse::ok(tag))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{tag_id}",
+    tag = "tags",
+    description = "Deletes the tag and detaches it from every task.",
+    params(("tag_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The tag was deleted", body = EmptyResponse),
+        (status = 404, description = "No such tag", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_tag(State(state): State<AppState>, Path(tag_id): Path<i32>) -> ApiResult<()> {
+    if !state.repository.delete_tag(tag_id).await? {
+        return Err(AppError::TagNotFound(tag_id));
+    }
+
+    Ok(ApiResponse::empty())
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags/{tag_id}/merge",
+    tag = "tags",
+    description = "Moves the tag's tasks over to the `into` tag, then deletes it.",
+    params(("tag_id" = i32, Path)),
+    request_body = MergeTagReq,
+    responses(
+        (status = 200, description = "The tag the other one was merged into", body = ApiResponse<TagRow>),
+        (status = 400, description = "The body is not valid JSON", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "One of the tags does not exist", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body is not `application/json`", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The tag would be merged into itself", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn merge_tag(
+    State(state): State<AppState>,
+    Path(tag_id): Path<i32>,
+    ValidatedJson(merge): ValidatedJson<MergeTagReq>,
+) -> ApiResult<TagRow> {
+    if merge.into == tag_id {
+        return Err(AppError::InvalidBody(
+            "A tag cannot be merged into itself".to_owned(),
+        ));
+    }
+
+    if let Some(tag) = state.repository.merge_tags(tag_id, merge.into).await? {
+        return Ok(ApiResponse::ok(tag));
+    }
+
+    // Tell which of the two tags is missing.
+    match state.repository.find_tag(tag_id).await? {
+        Some(_) => Err(AppError::TagNotFound(merge.into)),
+        None => Err(AppError::TagNotFound(tag_id)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/tags/{tag_id}",
+    tag = "tasks",
+    description = "Attaches the tag to the task; attaching it again changes nothing.",
+    params(("task_id" = i32, Path), ("tag_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task with its tags", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 404, description = "No such live task or no such tag", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn attach_tag(
+    State(state): State<AppState>,
+    Path((task_id, tag_id)): Path<(i32, i32)>,
+) -> ApiResult<TaskRow> {
+    ensure_tag_exists(&state, tag_id).await?;
+
+    let task = state
+        .repository
+        .attach_tag(task_id, tag_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    let etag = task_etag(&task);
+    Ok(ApiResponse::ok(task).with_etag(&etag))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/tags/{tag_id}",
+    tag = "tasks",
+    description = "Detaches the tag from the task; detaching a tag the task does not have changes nothing.",
+    params(("task_id" = i32, Path), ("tag_id" = i32, Path)),
+    responses(
+        (status = 200, description = "The task with its tags", body = ApiResponse<TaskRow>, headers(("ETag" = String))),
+        (status = 404, description = "No such live task or no such tag", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn detach_tag(
+    State(state): State<AppState>,
+    Path((task_id, tag_id)): Path<(i32, i32)>,
+) -> ApiResult<TaskRow> {
+    ensure_tag_exists(&state, tag_id).await?;
+
+    let task = state
+        .repository
+        .detach_tag(task_id, tag_id)
+        .await?
+        .ok_or(AppError::TaskNotFound(task_id))?;
+
+    let etag = task_etag(&task);
+    Ok(ApiResponse::ok(task).with_etag(&etag))
+}
+
+async fn ensure_tag_exists(state: &AppState, tag_id: i32) -> Result<(), AppError> {
+    match state.repository.find_tag(tag_id).await? {
+        Some(_) => Ok(()),
+        None => Err(AppError::TagNotFound(tag_id)),
+    }
+}
+
+/// Words the unique violation of a duplicate tag name in terms of the name.
+fn name_conflict(err: sqlx::Error, name: &str) -> AppError {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            AppError::Conflict(format!("A tag named '{name}' already exists"))
+        }
+        _ => err.into(),
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use axum::{Json, extract::State, http::StatusCode};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::{Pool, Postgres};
+use tokio::time::timeout;
+
+use crate::{migrations, state::AppState};
+
+/// Bound on each database query of the readiness probe, so that it answers well within the
+/// orchestrator's own probe timeout instead of waiting for the pool's acquire timeout.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ComponentStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize)]
+struct ComponentCheck {
+    status: ComponentStatus,
+    #[serde(flatten)]
+    details: Value,
+}
+
+impl ComponentCheck {
+    fn up(details: Value) -> Self {
+        Self {
+            status: ComponentStatus::Up,
+            details,
+        }
+    }
+
+    fn down(details: Value) -> Self {
+        Self {
+            status: ComponentStatus::Down,
+            details,
+        }
+    }
+}
+
+/// Liveness probe: answers as long as the process is able to serve requests at all.
+pub async fn liveness() -> Json<Value> {
+    Json(json!({"status": "alive"}))
+}
+
+/// Readiness probe: fails while draining for shutdown or when any database component is down.
+pub async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let mut checks = serde_json::Map::new();
+
+    checks.insert(
+        "shutdown".to_owned(),
+        json!(if state.is_draining() {
+            ComponentCheck::down(json!({"draining": true}))
+        } else {
+            ComponentCheck::up(json!({"draining": false}))
+        }),
+    );
+
+    if let Some(db_pool) = &state.db_pool {
+        let pool = check_pool(db_pool);
+
+        // Querying an exhausted pool would only queue behind the busy connections.
+        let (database, migrations) = match pool.status {
+            ComponentStatus::Up => (
+                check_database(db_pool).await,
+                check_migrations(db_pool).await,
+            ),
+            ComponentStatus::Down => (
+                ComponentCheck::down(json!({"error": "pool exhausted"})),
+                ComponentCheck::down(json!({"error": "pool exhausted"})),
+            ),
+        };
+
+        checks.insert("pool".to_owned(), json!(pool));
+        checks.insert("database".to_owned(), json!(database));
+        checks.insert("migrations".to_owned(), json!(migrations));
+    }
+
+    let ready = checks
+        .values()
+        .all(|check| check["status"] == json!(ComponentStatus::Up));
+
+    let (status_code, status) = match ready {
+        true => (StatusCode::OK, "ready"),
+        false => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+    };
+
+    (
+        status_code,
+        Json(json!({"status": status, "checks": checks})),
+    )
+}
+
+fn check_pool(db_pool: &Pool<Postgres>) -> ComponentCheck {
+    let size = db_pool.size();
+    let idle = db_pool.num_idle();
+    let max = db_pool.options().get_max_connections();
+
+    let details = json!({"size": size, "idle": idle, "max": max});
+
+    if size >= max && idle == 0 {
+        return ComponentCheck::down(details);
+    }
+
+    ComponentCheck::up(details)
+}
+
+async fn check_database(db_pool: &Pool<Postgres>) -> ComponentCheck {
+    let started_at = Instant::now();
+
+    match timeout(QUERY_TIMEOUT, sqlx::query("SELECT 1").execute(db_pool)).await {
+        Ok(Ok(_)) => ComponentCheck::up(json!({"latency_ms": started_at.elapsed().as_millis()})),
+        Ok(Err(err)) => query_failed("database", &err),
+        Err(_) => query_timed_out("database"),
+    }
+}
+
+async fn check_migrations(db_pool: &Pool<Postgres>) -> ComponentCheck {
+    match timeout(QUERY_TIMEOUT, migrations::pending_count(db_pool)).await {
+        Ok(Ok(0)) => ComponentCheck::up(json!({"pending": 0})),
+        Ok(Ok(pending)) => ComponentCheck::down(json!({"pending": pending})),
+        Ok(Err(err)) => query_failed("migrations", &err),
+        Err(_) => query_timed_out("migrations"),
+    }
+}
+
+/// The probe is unauthenticated, so the error itself only goes to the logs.
+fn query_failed(component: &str, err: &impl Display) -> ComponentCheck {
+    tracing::warn!(component, error = %err, "Readiness check failed");
+
+    ComponentCheck::down(json!({"error": "query failed"}))
+}
+
+fn query_timed_out(component: &str) -> ComponentCheck {
+    tracing::warn!(
+        component,
+        timeout_ms = QUERY_TIMEOUT.as_millis(),
+        "Readiness check timed out"
+    );
+
+    ComponentCheck::down(json!({"error": "timed out"}))
+}
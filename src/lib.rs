@@ -0,0 +1,141 @@
+//! A tasks CRUD API on top of axum, embeddable into other axum services.
+//!
+//! ```no_run
+//! use axum::Router;
+//! use first_axum_postgres_crud::{AppState, build_router};
+//!
+//! let tasks = build_router(AppState::in_memory());
+//! let app: Router = Router::new().nest("/tasks", tasks);
+//! ```
+
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{get, post, put},
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+pub mod attachments;
+pub mod blob_store;
+pub mod config;
+pub mod dependencies;
+pub mod error;
+pub mod etag;
+mod extract;
+mod handlers;
+pub mod health;
+pub mod markdown;
+pub mod metrics;
+pub mod migrations;
+pub mod models;
+pub mod openapi;
+pub mod patch;
+pub mod query;
+pub mod repository;
+pub mod response;
+pub mod search;
+pub mod shutdown;
+pub mod state;
+pub mod telemetry;
+pub mod workflow;
+
+pub use state::{AppState, DeleteMode, SubtasksOnDelete};
+
+/// Routes of the tasks API, relative to wherever the router gets nested.
+pub fn build_router(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(handlers::get_tasks).post(handlers::create_task))
+        .route("/trash", get(handlers::get_trashed_tasks))
+        .route("/search", get(handlers::search_tasks))
+        .route("/order", get(handlers::dependencies::get_execution_order))
+        .route(
+            "/:task_id",
+            get(handlers::get_task)
+                .put(handlers::replace_task)
+                .patch(handlers::update_task)
+                .delete(handlers::delete_task),
+        )
+        .route("/:task_id/restore", post(handlers::restore_task))
+        .route("/:task_id/children", get(handlers::subtasks::get_children))
+        .route("/:task_id/subtree", get(handlers::subtasks::get_subtree))
+        .route("/:task_id/start", post(handlers::start_task))
+        .route("/:task_id/block", post(handlers::block_task))
+        .route("/:task_id/complete", post(handlers::complete_task))
+        .route("/:task_id/cancel", post(handlers::cancel_task))
+        .route("/:task_id/reopen", post(handlers::reopen_task))
+        .route(
+            "/:task_id/dependencies",
+            get(handlers::dependencies::get_dependencies),
+        )
+        .route(
+            "/:task_id/dependencies/:blocker_task_id",
+            put(handlers::dependencies::add_dependency)
+                .delete(handlers::dependencies::remove_dependency),
+        )
+        .route(
+            "/:task_id/tags/:tag_id",
+            put(handlers::tags::attach_tag).delete(handlers::tags::detach_tag),
+        )
+        .route(
+            "/:task_id/attachments",
+            get(handlers::attachments::get_attachments)
+                .post(handlers::attachments::upload_attachment)
+                .layer(DefaultBodyLimit::max(attachments::body_limit(
+                    state.max_attachment_bytes,
+                ))),
+        )
+        .route(
+            "/:task_id/attachments/:attachment_id",
+            get(handlers::attachments::download_attachment)
+                .delete(handlers::attachments::delete_attachment),
+        )
+        .route(
+            "/:task_id/comments",
+            get(handlers::comments::get_comments).post(handlers::comments::create_comment),
+        )
+        .route(
+            "/:task_id/comments/:comment_id",
+            get(handlers::comments::get_comment)
+                .patch(handlers::comments::update_comment)
+                .delete(handlers::comments::delete_comment),
+        )
+        .with_state(state)
+}
+
+/// Routes of the tags shared by every task, relative to wherever the router gets nested.
+pub fn build_tags_router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(handlers::tags::get_tags).post(handlers::tags::create_tag),
+        )
+        .route(
+            "/:tag_id",
+            get(handlers::tags::get_tag)
+                .patch(handlers::tags::update_tag)
+                .delete(handlers::tags::delete_tag),
+        )
+        .route("/:tag_id/merge", post(handlers::tags::merge_tag))
+        .with_state(state)
+}
+
+/// The standalone application served by the binary: the tasks API under `/tasks` and
+/// `/tags`, its OpenAPI document and docs UI, and request tracing and metrics on every route.
+pub fn build_app(state: AppState) -> Router {
+    let app = Router::new()
+        .route("/", get(|| async { "Hello, World\n" }))
+        .route("/healthz", get(health::liveness))
+        .route("/readyz", get(health::readiness))
+        .route("/metrics", get(metrics::render))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi::spec()))
+        .with_state(state.clone())
+        .nest("/tasks", build_router(state.clone()))
+        .nest("/tags", build_tags_router(state.clone()))
+        .layer(middleware::from_fn_with_state(
+            state,
+            metrics::track_requests,
+        ));
+
+    telemetry::trace_requests(app)
+}
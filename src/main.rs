@@ -1,197 +1,198 @@
-use std::{env, time::Duration};
-
-use axum::{
-    Json, Router,
-    extract::{Path, State},
-    http::StatusCode,
-    routing::get,
+use std::{str::FromStr, sync::Arc};
+
+use chrono::Utc;
+use clap::Parser;
+use object_store::aws::AmazonS3Builder;
+use sqlx::{
+    ConnectOptions, Pool, Postgres,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use tokio::{net::TcpListener, time::timeout};
+
+use first_axum_postgres_crud::{
+    AppState, attachments,
+    blob_store::{InMemoryBlobStore, LocalBlobStore, S3BlobStore, SharedBlobStore},
+    build_app,
+    config::{
+        AttachmentStore, AttachmentsConfig, Cli, Command, Config, DatabaseConfig, StorageBackend,
+    },
+    metrics::Metrics,
+    migrations,
+    repository::{InMemoryTaskRepository, PgTaskRepository, SharedTaskRepository},
+    shutdown, telemetry,
 };
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sqlx::{Error, Pool, Postgres, postgres::PgPoolOptions};
-use tokio::net::TcpListener;
-
-#[derive(Clone, Serialize)]
-struct TaskRow {
-    task_id: i32,
-    name: String,
-    priority: Option<i32>,
-}
 
-async fn load_task_by_id(
-    pg_pool: &Pool<Postgres>,
-    task_id: &i32,
-) -> Result<Option<TaskRow>, Error> {
-    sqlx::query_as!(TaskRow, "SELECT * FROM tasks WHERE task_id = $1", task_id)
-        .fetch_optional(pg_pool)
+async fn connect_to_database(database: &DatabaseConfig) -> Pool<Postgres> {
+    let database_url = database
+        .url
+        .as_deref()
+        .expect("The database URL is not configured, set DATABASE_URL or --database-url");
+
+    // Every statement is logged with its duration under the `sqlx::query` target.
+    let connect_options = PgConnectOptions::from_str(database_url)
+        .expect("Invalid database URL")
+        .log_statements(log::LevelFilter::Debug)
+        .log_slow_statements(log::LevelFilter::Warn, database.slow_statement_threshold());
+
+    PgPoolOptions::new()
+        .max_connections(database.max_connections)
+        .min_connections(database.min_connections)
+        .acquire_timeout(database.acquire_timeout())
+        .idle_timeout(database.idle_timeout())
+        .connect_with(connect_options)
         .await
+        .expect("Could not connect to the database")
 }
 
-fn map_pg_error(pg_err: sqlx::Error) -> (StatusCode, String) {
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        json!({"success": false, "message": pg_err.to_string()}).to_string(),
-    )
-}
+/// Builds the configured repository, along with its pool when it is backed by Postgres.
+async fn build_repository(
+    config: &Config,
+    metrics: &Metrics,
+) -> (SharedTaskRepository, Option<Pool<Postgres>>) {
+    if config.storage == StorageBackend::Memory {
+        return (Arc::new(InMemoryTaskRepository::new()), None);
+    }
 
-fn build_not_found_error(task_id: i32) -> (StatusCode, String) {
-    (
-        StatusCode::NOT_FOUND,
-        json!({"message": format!("Task {task_id} not found")}).to_string(),
-    )
-}
+    let db_pool = connect_to_database(&config.database).await;
 
-fn map_success(status_code: StatusCode, data: Option<impl Serialize>) -> (StatusCode, String) {
-    if data.is_none() {
-        return (status_code, json!({"success": true}).to_string());
+    if !config.database.skip_migrations {
+        migrations::run(&db_pool)
+            .await
+            .expect("Could not apply the database migrations");
     }
 
     (
-        status_code,
-        json!({"success": true, "data": data}).to_string(),
+        Arc::new(PgTaskRepository::new(db_pool.clone()).with_metrics(metrics)),
+        Some(db_pool),
     )
 }
 
-async fn get_tasks(
-    State(pg_pool): State<Pool<Postgres>>,
-) -> Result<(StatusCode, String), (StatusCode, String)> {
-    let rows = sqlx::query_as!(TaskRow, "SELECT * FROM tasks ORDER BY task_id")
-        .fetch_all(&pg_pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                json!({"success": false, "message": e.to_string()}).to_string(),
-            )
-        })?;
-
-    Ok((
-        StatusCode::OK,
-        json!({"success": true, "data": rows}).to_string() + "\n",
-    ))
+/// Builds the configured store of attachment files.
+fn build_blob_store(attachments: &AttachmentsConfig) -> SharedBlobStore {
+    match attachments.store {
+        AttachmentStore::Local => Arc::new(LocalBlobStore::new(&attachments.dir)),
+        AttachmentStore::Memory => Arc::new(InMemoryBlobStore::new()),
+        AttachmentStore::S3 => {
+            let s3 = &attachments.s3;
+            let bucket = s3
+                .bucket
+                .as_deref()
+                .expect("The S3 bucket is not configured, set S3_BUCKET or --s3-bucket");
+
+            let mut builder = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .with_region(&s3.region)
+                .with_allow_http(s3.allow_http);
+
+            if let Some(endpoint) = &s3.endpoint {
+                builder = builder.with_endpoint(endpoint);
+            }
+            if let Some(access_key_id) = &s3.access_key_id {
+                builder = builder.with_access_key_id(access_key_id);
+            }
+            if let Some(secret_access_key) = &s3.secret_access_key {
+                builder = builder.with_secret_access_key(secret_access_key);
+            }
+
+            let bucket = builder.build().expect("Invalid S3 configuration");
+
+            Arc::new(S3BlobStore::new(bucket))
+        }
+    }
 }
 
-async fn get_task(
-    State(pg_pool): State<Pool<Postgres>>,
-    Path(task_id): Path<i32>,
-) -> Result<(StatusCode, String), (StatusCode, String)> {
-    let task = load_task_by_id(&pg_pool, &task_id)
-        .await
-        .map_err(map_pg_error)?;
+async fn purge_trash(config: &Config) {
+    let retention_days = config.tasks.trash_retention_days;
 
-    if task.is_none() {
-        return Err(build_not_found_error(task_id));
-    }
+    let db_pool = connect_to_database(&config.database).await;
+    let repository: SharedTaskRepository = Arc::new(PgTaskRepository::new(db_pool));
 
-    Ok(map_success(StatusCode::OK, task))
-}
-
-#[derive(Deserialize)]
-struct CreateTaskReq {
-    name: String,
-    priority: Option<i32>,
-}
+    let purged = repository
+        .purge(Utc::now() - chrono::Duration::days(retention_days))
+        .await
+        .expect("Could not purge the trash");
 
-#[derive(Serialize)]
-struct CreateTaskRow {
-    task_id: i32,
-}
+    tracing::info!(purged, retention_days, "Purged tasks from the trash");
 
-async fn create_task(
-    State(pg_pool): State<Pool<Postgres>>,
-    Json(task): Json<CreateTaskReq>,
-) -> Result<(StatusCode, String), (StatusCode, String)> {
-    let row = sqlx::query_as!(
-        CreateTaskRow,
-        "INSERT INTO tasks (name, priority) VALUES ($1, $2) RETURNING task_id",
-        task.name,
-        task.priority
+    // Files younger than an hour may belong to uploads that are still under way.
+    let blob_store = build_blob_store(&config.attachments);
+    let deleted_files = attachments::sweep_orphaned_blobs(
+        &repository,
+        &blob_store,
+        Utc::now() - chrono::Duration::hours(1),
     )
-    .fetch_one(&pg_pool)
     .await
-    .map_err(map_pg_error)?;
+    .expect("Could not delete the unused attachment files");
 
-    Ok(map_success(StatusCode::OK, Some(row)))
+    tracing::info!(deleted_files, "Deleted unused attachment files");
 }
 
-#[derive(Deserialize)]
-struct UpdateTaskReq {
-    name: Option<String>,
-    priority: Option<i32>,
-}
+async fn serve(config: &Config) {
+    let metrics = Metrics::new();
+    let (repository, db_pool) = build_repository(config, &metrics).await;
+    let mut state = AppState::new(repository)
+        .with_delete_mode(config.tasks.delete_mode)
+        .with_subtasks_on_delete(config.tasks.subtasks_on_delete)
+        .with_transitions(config.tasks.transitions.clone())
+        .with_blob_store(build_blob_store(&config.attachments))
+        .with_max_attachment_bytes(config.attachments.max_size_bytes)
+        .with_metrics(metrics);
+
+    if let Some(db_pool) = &db_pool {
+        state = state.with_db_pool(db_pool.clone());
+    }
 
-async fn update_task(
-    State(pg_pool): State<Pool<Postgres>>,
-    Path(task_id): Path<i32>,
-    Json(task): Json<UpdateTaskReq>,
-) -> Result<(StatusCode, String), (StatusCode, String)> {
-    let original_task = load_task_by_id(&pg_pool, &task_id)
+    let listener = TcpListener::bind(&config.server.address)
         .await
-        .map_err(map_pg_error)?;
+        .expect("Could not create TCP Listener");
 
-    if original_task.is_none() {
-        return Err(build_not_found_error(task_id));
-    }
+    tracing::info!(address = %listener.local_addr().unwrap(), "Listening");
 
-    let original_task = original_task.unwrap();
-    let task_name = task.name.unwrap_or(original_task.name);
-    let task_priority = task.priority.or(original_task.priority);
+    let app = build_app(state.clone());
 
-    sqlx::query!(
-        "UPDATE tasks SET name = $2, priority = $3 WHERE task_id = $1",
-        task_id,
-        task_name,
-        task_priority
-    )
-    .execute(&pg_pool)
-    .await
-    .map_err(map_pg_error)?;
+    shutdown::serve(listener, app, state, config.server.drain_options())
+        .await
+        .expect("Error serving the application");
 
-    Ok((StatusCode::OK, json!({"success": true}).to_string()))
-}
+    let pool_close_timeout = config.server.pool_close_timeout();
 
-async fn delete_task(
-    State(pg_pool): State<Pool<Postgres>>,
-    Path(task_id): Path<i32>,
-) -> Result<(StatusCode, String), (StatusCode, String)> {
-    sqlx::query!("DELETE FROM tasks WHERE task_id = $1", task_id)
-        .execute(&pg_pool)
-        .await
-        .map_err(map_pg_error)?;
+    if let Some(db_pool) = db_pool
+        && timeout(pool_close_timeout, db_pool.close()).await.is_err()
+    {
+        tracing::warn!(
+            close_timeout_secs = pool_close_timeout.as_secs(),
+            "Database connections still in use were abandoned"
+        );
+    }
 
-    Ok(map_success(StatusCode::OK, None::<()>))
+    tracing::info!("Shut down cleanly");
 }
 
 #[tokio::main]
 async fn main() {
-    dotenvy::dotenv().expect("Unable to access .env file");
+    // The .env file is a development convenience; deployments set real environment variables.
+    dotenvy::dotenv().ok();
 
-    let server_address = env::var("SERVER_ADDRESS").unwrap_or("0.0.0.0:3000".to_owned());
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not found in the env file");
+    let cli = Cli::parse();
+    let config = Config::load(&cli).unwrap_or_else(|err| panic!("{err}"));
 
-    let db_pool = PgPoolOptions::new()
-        .max_connections(16)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect(&database_url)
-        .await
-        .expect("Could not connect to the database");
-
-    let listener = TcpListener::bind(server_address)
-        .await
-        .expect("Could not create TCP Listener");
+    if cli.print_config {
+        print!("{}", config.to_redacted_toml());
+        return;
+    }
 
-    println!("Listening on {}", listener.local_addr().unwrap());
+    telemetry::init(&config.logging);
 
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World\n" }))
-        .route("/tasks", get(get_tasks).post(create_task))
-        .route(
-            "/tasks/:task_id",
-            get(get_task).patch(update_task).delete(delete_task),
-        )
-        .with_state(db_pool);
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(&config).await,
+        Command::Migrations => {
+            let db_pool = connect_to_database(&config.database).await;
+            let statuses = migrations::status(&db_pool)
+                .await
+                .expect("Could not read the database migrations");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Error serving the application")
+            migrations::print_status(&statuses);
+        }
+        Command::Purge => purge_trash(&config).await,
+    }
 }
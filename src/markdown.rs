@@ -0,0 +1,15 @@
+use pulldown_cmark::{Options, Parser, html};
+
+/// Renders CommonMark, with tables and strikethrough, to HTML that is safe to embed in a
+/// page: scripts, event handlers and other dangerous markup are stripped.
+pub fn to_safe_html(markdown: &str) -> String {
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    );
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::clean(&unsafe_html)
+}
@@ -0,0 +1,183 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::state::AppState;
+
+/// Route label of requests that did not match any route, so unknown paths cannot
+/// blow up the number of series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Prometheus collectors of the service, rendered by `GET /metrics`.
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<MetricsInner>,
+}
+
+struct MetricsInner {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    errors: IntCounterVec,
+    pool_size: IntGauge,
+    pool_idle: IntGauge,
+    pool_max: IntGauge,
+    pool_waiting: IntGauge,
+    tasks: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to handle HTTP requests",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new(
+                "http_errors_total",
+                "HTTP requests answered with a 4xx or 5xx",
+            ),
+            &["status"],
+        )
+        .unwrap();
+        let pool_size = IntGauge::new("db_pool_size", "Open database connections").unwrap();
+        let pool_idle = IntGauge::new("db_pool_idle", "Database connections not in use").unwrap();
+        let pool_max =
+            IntGauge::new("db_pool_max_size", "Maximum number of database connections").unwrap();
+        let pool_waiting = IntGauge::new(
+            "db_pool_waiting_acquires",
+            "Queries waiting for a database connection",
+        )
+        .unwrap();
+        let tasks = IntGauge::new("tasks_total", "Live tasks, excluding the trash").unwrap();
+
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry
+            .register(Box::new(request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(errors.clone())).unwrap();
+        registry.register(Box::new(pool_size.clone())).unwrap();
+        registry.register(Box::new(pool_idle.clone())).unwrap();
+        registry.register(Box::new(pool_max.clone())).unwrap();
+        registry.register(Box::new(pool_waiting.clone())).unwrap();
+        registry.register(Box::new(tasks.clone())).unwrap();
+
+        Self {
+            inner: Arc::new(MetricsInner {
+                registry,
+                requests,
+                request_duration,
+                errors,
+                pool_size,
+                pool_idle,
+                pool_max,
+                pool_waiting,
+                tasks,
+            }),
+        }
+    }
+
+    /// Gauge of queries waiting for a pooled connection, kept up to date by
+    /// [`crate::repository::PgTaskRepository`].
+    pub fn pool_waiting(&self) -> IntGauge {
+        self.inner.pool_waiting.clone()
+    }
+
+    fn observe(&self, method: &str, route: &str, status: StatusCode, seconds: f64) {
+        let inner = &self.inner;
+
+        inner
+            .requests
+            .with_label_values(&[method, route, status.as_str()])
+            .inc();
+        inner
+            .request_duration
+            .with_label_values(&[method, route])
+            .observe(seconds);
+
+        if status.is_client_error() || status.is_server_error() {
+            inner.errors.with_label_values(&[status.as_str()]).inc();
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware counting and timing every request by method, route template and status.
+pub async fn track_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE.to_owned(), |path| path.as_str().to_owned());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    state.metrics.observe(
+        &method,
+        &route,
+        response.status(),
+        started.elapsed().as_secs_f64(),
+    );
+
+    response
+}
+
+/// `GET /metrics`: refreshes the pool and task gauges, then renders every collector
+/// in the Prometheus text format.
+pub async fn render(State(state): State<AppState>) -> Response {
+    let inner = &state.metrics.inner;
+
+    if let Some(db_pool) = &state.db_pool {
+        inner.pool_size.set(db_pool.size().into());
+        inner.pool_idle.set(db_pool.num_idle() as i64);
+        inner
+            .pool_max
+            .set(db_pool.options().get_max_connections().into());
+    }
+
+    match state.repository.count().await {
+        Ok(count) => inner.tasks.set(count),
+        Err(err) => tracing::warn!(error = %err, "Could not count the tasks for the metrics"),
+    }
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    encoder
+        .encode(&inner.registry.gather(), &mut body)
+        .expect("The metrics are always encodable");
+
+    (
+        [(header::CONTENT_TYPE, encoder.format_type().to_owned())],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use sqlx::{
+    Pool, Postgres,
+    migrate::{Migrate, MigrateError, Migrator},
+};
+
+/// Migrations from the `migrations` directory, embedded in the binary at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+pub enum MigrationState {
+    Applied,
+    Pending,
+    ChecksumMismatch,
+}
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+pub async fn run(pg_pool: &Pool<Postgres>) -> Result<(), MigrateError> {
+    MIGRATOR.run(pg_pool).await
+}
+
+/// Number of embedded migrations not applied yet, without creating the bookkeeping table.
+pub async fn pending_count(pg_pool: &Pool<Postgres>) -> Result<usize, MigrateError> {
+    let mut conn = pg_pool.acquire().await?;
+
+    let applied: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .count())
+}
+
+pub async fn status(pg_pool: &Pool<Postgres>) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pg_pool.acquire().await?;
+
+    conn.ensure_migrations_table().await?;
+
+    let applied: HashMap<i64, _> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect();
+
+    let statuses = MIGRATOR
+        .iter()
+        .map(|migration| {
+            let state = match applied.get(&migration.version) {
+                None => MigrationState::Pending,
+                Some(checksum) if *checksum != migration.checksum => {
+                    MigrationState::ChecksumMismatch
+                }
+                Some(_) => MigrationState::Applied,
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+pub fn print_status(statuses: &[MigrationStatus]) {
+    for status in statuses {
+        let state = match status.state {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::ChecksumMismatch => "checksum mismatch",
+        };
+
+        println!("{} {:<18} {}", status.version, state, status.description);
+    }
+}
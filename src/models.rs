@@ -0,0 +1,402 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::{markdown, patch::PatchField};
+
+pub const MAX_NAME_LENGTH: u64 = 200;
+pub const MIN_PRIORITY: i32 = 0;
+pub const MAX_PRIORITY: i32 = 10;
+pub const MAX_TAG_NAME_LENGTH: u64 = 50;
+pub const DEFAULT_TAG_COLOR: &str = "#808080";
+pub const MAX_AUTHOR_LENGTH: u64 = 100;
+pub const MAX_COMMENT_LENGTH: u64 = 10_000;
+
+/// Where a task is in its workflow; moves between statuses are limited by
+/// [`crate::workflow::Transitions`].
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "task_status", rename_all = "snake_case")]
+pub enum TaskStatus {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the task needs no more work, i.e. it is `done` or `cancelled`.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, TaskStatus::Done | TaskStatus::Cancelled)
+    }
+}
+
+#[derive(Clone, Serialize, FromRow, ToSchema)]
+pub struct TaskRow {
+    #[schema(example = 1)]
+    pub task_id: i32,
+    /// Task this one is a subtask of.
+    #[schema(example = json!(null))]
+    pub parent_task_id: Option<i32>,
+    #[schema(example = "Write the report")]
+    pub name: String,
+    #[schema(example = 3)]
+    pub priority: Option<i32>,
+    pub status: TaskStatus,
+    /// Percent of the subtasks, at any depth, that are done, leaving out cancelled and
+    /// trashed ones; `null` when there are none.
+    #[schema(minimum = 0, maximum = 100, example = 50)]
+    pub progress: Option<i32>,
+    /// Whether every live task blocking this one is `done`; `true` without blockers.
+    pub blockers_done: bool,
+    #[schema(example = 2)]
+    pub comment_count: i64,
+    /// Tags attached to the task, by name.
+    #[sqlx(json)]
+    pub tags: Vec<TagRow>,
+    /// When the task should be done by.
+    pub due_at: Option<DateTime<Utc>>,
+    /// When work on the task is planned to start.
+    pub start_at: Option<DateTime<Utc>>,
+    /// When the task was last completed; `null` unless its status is `done`.
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Time of the last write, which also bumped `version`.
+    pub updated_at: DateTime<Utc>,
+    /// Incremented on every write; the task's `ETag` is derived from it.
+    #[schema(example = 1)]
+    pub version: i32,
+    /// When the task was moved to the trash; only present for trashed tasks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TagRow {
+    #[schema(example = 1)]
+    pub tag_id: i32,
+    #[schema(example = "urgent")]
+    pub name: String,
+    /// `#rrggbb` hex color.
+    #[schema(example = "#d73a4a")]
+    pub color: String,
+}
+
+#[derive(Clone, Serialize, FromRow, ToSchema)]
+pub struct CommentRow {
+    #[schema(example = 1)]
+    pub comment_id: i32,
+    #[schema(example = 1)]
+    pub task_id: i32,
+    #[schema(example = "alice")]
+    pub author: String,
+    /// Markdown source, as written.
+    #[schema(example = "Looks good, see the **summary**")]
+    pub body: String,
+    /// `body` rendered to sanitized HTML, set by [`CommentRow::with_html`].
+    #[sqlx(skip)]
+    #[schema(example = "<p>Looks good, see the <strong>summary</strong></p>\n")]
+    pub body_html: String,
+    pub created_at: DateTime<Utc>,
+    /// Time of the last edit of `body`.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A task matching a search.
+#[derive(Serialize, ToSchema)]
+pub struct SearchHit {
+    pub task: TaskRow,
+    /// Relevance to the search, higher first; only comparable within the same search.
+    #[schema(example = 0.06)]
+    pub rank: f32,
+    /// The task's name as HTML, with the matching words wrapped in `<mark>`.
+    #[schema(example = "<mark>Fix</mark> the <mark>login</mark> page")]
+    pub snippet: String,
+}
+
+/// Metadata of a file attached to a task; the file itself is in the blob store.
+#[derive(Clone, Serialize, FromRow, ToSchema)]
+pub struct AttachmentRow {
+    #[schema(example = 1)]
+    pub attachment_id: i32,
+    #[schema(example = 1)]
+    pub task_id: i32,
+    /// Name of the uploaded file, without any directory.
+    #[schema(example = "screenshot.png")]
+    pub file_name: String,
+    /// Detected from the contents, whatever the client declared.
+    #[schema(example = "image/png")]
+    pub content_type: String,
+    /// In bytes.
+    #[schema(example = 48213)]
+    pub size: i64,
+    /// Key of the file in the blob store.
+    #[serde(skip)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An attachment whose file is already in the blob store, to record.
+pub struct NewAttachment {
+    pub task_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub storage_key: String,
+}
+
+impl CommentRow {
+    /// Renders `body` into `body_html`, for responses.
+    pub fn with_html(self) -> Self {
+        Self {
+            body_html: markdown::to_safe_html(&self.body),
+            ..self
+        }
+    }
+}
+
+impl TaskRow {
+    /// Whether the task is still open past its due time.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        !self.status.is_finished() && self.due_at.is_some_and(|due_at| due_at < now)
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateTaskReq {
+    #[validate(
+        length(min = 1, max = MAX_NAME_LENGTH, message = "must be between 1 and 200 characters long"),
+        custom(function = "validate_task_name")
+    )]
+    #[schema(min_length = 1, max_length = 200, example = "Write the report")]
+    pub name: String,
+    #[validate(range(min = MIN_PRIORITY, max = MAX_PRIORITY, message = "must be between 0 and 10"))]
+    #[schema(minimum = 0, maximum = 10, example = 3)]
+    pub priority: Option<i32>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub start_at: Option<DateTime<Utc>>,
+    /// Makes the task a subtask of this live task.
+    pub parent_task_id: Option<i32>,
+}
+
+#[derive(Serialize, FromRow, ToSchema)]
+pub struct CreateTaskRow {
+    #[schema(example = 1)]
+    pub task_id: i32,
+}
+
+/// JSON Merge Patch of a task: absent fields are left alone and `null` clears `priority`.
+/// Any other field, such as `status`, is rejected rather than silently ignored.
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateTaskReq {
+    #[serde(default)]
+    #[schema(value_type = String, min_length = 1, max_length = 200, example = "Write the report")]
+    pub name: PatchField<String>,
+    #[serde(default)]
+    #[schema(value_type = Option<i32>, minimum = 0, maximum = 10, example = 3)]
+    pub priority: PatchField<i32>,
+    #[serde(default)]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub due_at: PatchField<DateTime<Utc>>,
+    #[serde(default)]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub start_at: PatchField<DateTime<Utc>>,
+    /// `null` turns the task back into a top-level one.
+    #[serde(default)]
+    #[schema(value_type = Option<i32>)]
+    pub parent_task_id: PatchField<i32>,
+}
+
+/// Full representation of a task's editable fields, as sent to `PUT /tasks/:task_id` or
+/// produced by a JSON Patch. Any other field, such as `status`, is rejected.
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReplaceTaskReq {
+    #[validate(
+        length(min = 1, max = MAX_NAME_LENGTH, message = "must be between 1 and 200 characters long"),
+        custom(function = "validate_task_name")
+    )]
+    #[schema(min_length = 1, max_length = 200, example = "Write the report")]
+    pub name: String,
+    #[validate(range(min = MIN_PRIORITY, max = MAX_PRIORITY, message = "must be between 0 and 10"))]
+    #[schema(minimum = 0, maximum = 10, example = 3)]
+    pub priority: Option<i32>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub start_at: Option<DateTime<Utc>>,
+    /// Makes the task a subtask of this live task.
+    pub parent_task_id: Option<i32>,
+}
+
+/// Changes to store on a task; `None` leaves the field as it is.
+#[derive(Default, Validate)]
+pub struct TaskChanges {
+    #[validate(
+        length(min = 1, max = MAX_NAME_LENGTH, message = "must be between 1 and 200 characters long"),
+        custom(function = "validate_task_name")
+    )]
+    pub name: Option<String>,
+    #[validate(range(min = MIN_PRIORITY, max = MAX_PRIORITY, message = "must be between 0 and 10"))]
+    pub priority: Option<Option<i32>>,
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    pub parent_task_id: Option<Option<i32>>,
+}
+
+impl TaskChanges {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.priority.is_none()
+            && self.due_at.is_none()
+            && self.start_at.is_none()
+            && self.parent_task_id.is_none()
+    }
+}
+
+impl From<ReplaceTaskReq> for TaskChanges {
+    fn from(task: ReplaceTaskReq) -> Self {
+        Self {
+            name: Some(task.name),
+            priority: Some(task.priority),
+            due_at: Some(task.due_at),
+            start_at: Some(task.start_at),
+            parent_task_id: Some(task.parent_task_id),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateTagReq {
+    #[validate(
+        length(min = 1, max = MAX_TAG_NAME_LENGTH, message = "must be between 1 and 50 characters long"),
+        custom(function = "validate_tag_name")
+    )]
+    #[schema(min_length = 1, max_length = 50, example = "urgent")]
+    pub name: String,
+    /// Defaults to gray, `#808080`.
+    #[validate(custom(function = "validate_color"))]
+    #[schema(example = "#d73a4a")]
+    pub color: Option<String>,
+}
+
+/// Renames and/or recolors a tag; absent fields are left alone.
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct UpdateTagReq {
+    #[validate(
+        length(min = 1, max = MAX_TAG_NAME_LENGTH, message = "must be between 1 and 50 characters long"),
+        custom(function = "validate_tag_name")
+    )]
+    #[schema(min_length = 1, max_length = 50, example = "urgent")]
+    pub name: Option<String>,
+    #[validate(custom(function = "validate_color"))]
+    #[schema(example = "#d73a4a")]
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct MergeTagReq {
+    /// Tag that takes over the tasks of the merged one.
+    #[schema(example = 2)]
+    pub into: i32,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateCommentReq {
+    /// Who wrote the comment.
+    #[validate(
+        length(min = 1, max = MAX_AUTHOR_LENGTH, message = "must be between 1 and 100 characters long"),
+        custom(function = "validate_not_blank")
+    )]
+    #[schema(min_length = 1, max_length = 100, example = "alice")]
+    pub author: String,
+    /// Markdown; HTML in it is sanitized when rendered.
+    #[validate(
+        length(min = 1, max = MAX_COMMENT_LENGTH, message = "must be between 1 and 10000 characters long"),
+        custom(function = "validate_not_blank")
+    )]
+    #[schema(
+        min_length = 1,
+        max_length = 10000,
+        example = "Looks good, see the **summary**"
+    )]
+    pub body: String,
+}
+
+/// New body of a comment; its author cannot change.
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct UpdateCommentReq {
+    #[validate(
+        length(min = 1, max = MAX_COMMENT_LENGTH, message = "must be between 1 and 10000 characters long"),
+        custom(function = "validate_not_blank")
+    )]
+    #[schema(
+        min_length = 1,
+        max_length = 10000,
+        example = "Looks good, see the **summary**"
+    )]
+    pub body: String,
+}
+
+fn validate_task_name(value: &str) -> Result<(), ValidationError> {
+    validate_not_blank(value)?;
+
+    // Search highlights matches in names with control characters, see `search::MATCH_START`.
+    if value.chars().any(char::is_control) {
+        return Err(ValidationError::new("control_character")
+            .with_message("cannot contain control characters".into()));
+    }
+
+    Ok(())
+}
+
+fn validate_tag_name(value: &str) -> Result<(), ValidationError> {
+    validate_not_blank(value)?;
+
+    // Tag filters trim the names they are given, so such a tag could never be matched.
+    if value.trim() != value {
+        return Err(ValidationError::new("whitespace")
+            .with_message("cannot start or end with whitespace".into()));
+    }
+
+    // Tag filters take comma-separated names.
+    if value.contains(',') {
+        return Err(ValidationError::new("comma").with_message("cannot contain commas".into()));
+    }
+
+    Ok(())
+}
+
+fn validate_color(value: &str) -> Result<(), ValidationError> {
+    let is_hex_color = value.len() == 7
+        && value.starts_with('#')
+        && value[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if !is_hex_color {
+        return Err(ValidationError::new("color").with_message("must be a #rrggbb color".into()));
+    }
+
+    Ok(())
+}
+
+fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message("cannot be blank".into()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! OpenAPI 3.1 description of the tasks API, generated from the handlers and models.
+
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    error::{FieldError, Problem},
+    handlers,
+    models::{
+        AttachmentRow, CommentRow, CreateCommentReq, CreateTagReq, CreateTaskReq, CreateTaskRow,
+        MergeTagReq, ReplaceTaskReq, SearchHit, TagRow, TaskRow, TaskStatus, UpdateCommentReq,
+        UpdateTagReq, UpdateTaskReq,
+    },
+    response::PageMeta,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Tasks API",
+        description = "CRUD of tasks, with validation, optimistic concurrency, a trash, search, tags, comments and attachments."
+    ),
+    paths(
+        handlers::get_tasks,
+        handlers::create_task,
+        handlers::get_trashed_tasks,
+        handlers::search_tasks,
+        handlers::get_task,
+        handlers::replace_task,
+        handlers::update_task,
+        handlers::delete_task,
+        handlers::restore_task,
+        handlers::subtasks::get_children,
+        handlers::subtasks::get_subtree,
+        handlers::dependencies::get_execution_order,
+        handlers::dependencies::get_dependencies,
+        handlers::dependencies::add_dependency,
+        handlers::dependencies::remove_dependency,
+        handlers::start_task,
+        handlers::block_task,
+        handlers::complete_task,
+        handlers::cancel_task,
+        handlers::reopen_task,
+        handlers::comments::get_comments,
+        handlers::comments::create_comment,
+        handlers::comments::get_comment,
+        handlers::comments::update_comment,
+        handlers::comments::delete_comment,
+        handlers::attachments::get_attachments,
+        handlers::attachments::upload_attachment,
+        handlers::attachments::download_attachment,
+        handlers::attachments::delete_attachment,
+        handlers::tags::attach_tag,
+        handlers::tags::detach_tag,
+        handlers::tags::get_tags,
+        handlers::tags::create_tag,
+        handlers::tags::get_tag,
+        handlers::tags::update_tag,
+        handlers::tags::delete_tag,
+        handlers::tags::merge_tag,
+    ),
+    components(schemas(
+        TaskRow,
+        TaskStatus,
+        CreateTaskReq,
+        CreateTaskRow,
+        ReplaceTaskReq,
+        UpdateTaskReq,
+        JsonPatchOperation,
+        SearchHit,
+        TagRow,
+        CreateTagReq,
+        UpdateTagReq,
+        MergeTagReq,
+        CommentRow,
+        CreateCommentReq,
+        UpdateCommentReq,
+        AttachmentRow,
+        UploadAttachmentForm,
+        PageMeta,
+        EmptyResponse,
+        Problem,
+        FieldError,
+    )),
+    tags(
+        (name = "tasks", description = "Tasks and their trash"),
+        (name = "tags", description = "Tags that categorize tasks"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI document served at `/openapi.json`.
+pub fn spec() -> utoipa::openapi::OpenApi {
+    let mut spec = ApiDoc::openapi();
+    // The package declares no license, which would otherwise render with an empty name.
+    spec.info.license = None;
+    spec
+}
+
+/// Envelope of a successful response that carries no data.
+#[derive(Serialize, ToSchema)]
+pub struct EmptyResponse {
+    /// Always `true`.
+    success: bool,
+}
+
+/// One operation of an RFC 6902 JSON Patch document.
+#[derive(Serialize, ToSchema)]
+pub struct JsonPatchOperation {
+    /// `add`, `remove`, `replace`, `move`, `copy` or `test`
+    #[schema(example = "replace")]
+    op: String,
+    #[schema(example = "/name")]
+    path: String,
+    /// Source location of `move` and `copy`
+    from: Option<String>,
+    /// Value of `add`, `replace` and `test`
+    value: Option<Value>,
+}
+
+/// Form of an attachment upload.
+#[derive(Serialize, ToSchema)]
+pub struct UploadAttachmentForm {
+    /// The file, with its name
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+/// Raw contents of a file.
+#[derive(Serialize, ToSchema)]
+#[schema(value_type = String, format = Binary)]
+pub struct FileContents(Vec<u8>);
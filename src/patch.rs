@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::header,
+};
+use json_patch::{Patch, PatchErrorKind};
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use serde_json::{error::Category, json};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    error::AppError,
+    models::{ReplaceTaskReq, TaskChanges, TaskRow, UpdateTaskReq},
+};
+
+/// A field of a JSON Merge Patch (RFC 7396): absent leaves it unchanged, `null` clears it.
+#[derive(Default)]
+pub enum PatchField<T> {
+    #[default]
+    Unchanged,
+    Clear,
+    Set(T),
+}
+
+impl<T> PatchField<T> {
+    /// The change to a nullable column: `None` leaves it alone, `Some(None)` clears it.
+    fn into_nullable_change(self) -> Option<Option<T>> {
+        match self {
+            PatchField::Unchanged => None,
+            PatchField::Clear => Some(None),
+            PatchField::Set(value) => Some(Some(value)),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for PatchField<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Only called when the field is present, so a missing field stays `Unchanged`.
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => PatchField::Set(value),
+            None => PatchField::Clear,
+        })
+    }
+}
+
+/// Body of `PATCH /tasks/:task_id`, in the format announced by its `Content-Type`.
+pub enum TaskPatch {
+    /// `application/merge-patch+json` (RFC 7396), also accepted as plain `application/json`.
+    Merge(UpdateTaskReq),
+    /// `application/json-patch+json` (RFC 6902).
+    Json(Patch),
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for TaskPatch {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+        let constructor = match mime {
+            "application/json" | "application/merge-patch+json" => {
+                |bytes: &Bytes| parse_json(bytes).map(TaskPatch::Merge)
+            }
+            "application/json-patch+json" => |bytes: &Bytes| parse_json(bytes).map(TaskPatch::Json),
+            _ => {
+                return Err(AppError::UnsupportedMediaType(format!(
+                    "Expected application/merge-patch+json or application/json-patch+json, got '{content_type}'"
+                )));
+            }
+        };
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| AppError::MalformedBody(rejection.body_text()))?;
+
+        constructor(&bytes)
+    }
+}
+
+impl UpdateTaskReq {
+    /// Turns the merge patch into the changes it makes, without needing the current task.
+    pub fn into_changes(self) -> Result<TaskChanges, AppError> {
+        let mut errors = ValidationErrors::new();
+
+        let name = match self.name {
+            PatchField::Unchanged => None,
+            PatchField::Set(name) => Some(name),
+            PatchField::Clear => {
+                errors.add(
+                    "name",
+                    ValidationError::new("required").with_message("cannot be null".into()),
+                );
+                None
+            }
+        };
+
+        let changes = TaskChanges {
+            name,
+            priority: self.priority.into_nullable_change(),
+            due_at: self.due_at.into_nullable_change(),
+            start_at: self.start_at.into_nullable_change(),
+            parent_task_id: self.parent_task_id.into_nullable_change(),
+        };
+
+        if let Err(rule_errors) = changes.validate() {
+            errors.0.extend(rule_errors.0);
+        }
+
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Applies a JSON Patch on top of `task`, producing its full and validated replacement.
+pub fn apply_json_patch(patch: &Patch, task: &TaskRow) -> Result<ReplaceTaskReq, AppError> {
+    let mut document = json!({
+        "name": task.name,
+        "priority": task.priority,
+        "due_at": task.due_at,
+        "start_at": task.start_at,
+        "parent_task_id": task.parent_task_id,
+    });
+
+    json_patch::patch(&mut document, patch).map_err(|err| match err.kind {
+        PatchErrorKind::TestFailed => AppError::Conflict(err.to_string()),
+        _ => AppError::InvalidBody(err.to_string()),
+    })?;
+
+    let task: ReplaceTaskReq =
+        serde_json::from_value(document).map_err(|err| AppError::InvalidBody(err.to_string()))?;
+
+    task.validate().map_err(AppError::Validation)?;
+
+    Ok(task)
+}
+
+/// Tells malformed JSON (400) apart from well-formed JSON of the wrong shape (422).
+fn parse_json<T: DeserializeOwned>(bytes: &Bytes) -> Result<T, AppError> {
+    serde_json::from_slice(bytes).map_err(|err| match err.classify() {
+        Category::Data => AppError::InvalidBody(err.to_string()),
+        _ => AppError::MalformedBody(err.to_string()),
+    })
+}
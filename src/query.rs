@@ -0,0 +1,589 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Days, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::{
+    models::{CommentRow, SearchHit, TaskRow, TaskStatus},
+    search,
+};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+/// Raw query string of `GET /tasks`, validated into a [`TaskListQuery`].
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTasksParams {
+    /// Page size, from 1 to 100
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    limit: Option<i64>,
+    /// Tasks to skip; cannot be combined with `cursor`
+    #[param(minimum = 0, default = 0)]
+    offset: Option<i64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    /// `task_id`, `name` or `priority`, prefixed with `-` for descending order
+    #[param(example = "-priority")]
+    sort: Option<String>,
+    /// Exact status
+    status: Option<TaskStatus>,
+    /// Exact priority
+    priority: Option<i32>,
+    /// Lowest priority, inclusive
+    priority_min: Option<i32>,
+    /// Highest priority, inclusive
+    priority_max: Option<i32>,
+    /// Case-insensitive substring of the name
+    name_contains: Option<String>,
+    /// Only tasks due before this RFC 3339 date-time
+    #[param(format = DateTime, example = "2024-03-01T00:00:00Z")]
+    due_before: Option<String>,
+    /// Only tasks due at or after this RFC 3339 date-time
+    #[param(format = DateTime, example = "2024-02-01T00:00:00Z")]
+    due_after: Option<String>,
+    /// Only tasks due on this day in `tz`: `today` or a `YYYY-MM-DD` date
+    #[param(example = "today")]
+    due_on: Option<String>,
+    /// IANA timezone `due_on` is interpreted in (default `UTC`)
+    #[param(example = "Europe/Paris")]
+    tz: Option<String>,
+    /// `true` for open tasks past their due time, `false` for every other task
+    overdue: Option<bool>,
+    /// Comma-separated tag names, matching tasks with at least one of them
+    #[param(example = "urgent,home")]
+    tags_any: Option<String>,
+    /// Comma-separated tag names, matching tasks with every one of them
+    #[param(example = "urgent,home")]
+    tags_all: Option<String>,
+}
+
+/// Raw query string of `GET /tasks/{task_id}/comments`, validated into a [`CommentListQuery`].
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListCommentsParams {
+    /// Page size, from 1 to 100
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    limit: Option<i64>,
+    /// Comments to skip; cannot be combined with `cursor`
+    #[param(minimum = 0, default = 0)]
+    offset: Option<i64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+}
+
+/// Raw query string of `GET /tasks/search`, validated into a [`TaskSearchQuery`].
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchTasksParams {
+    /// Words to look for in task names; the last one may be incomplete
+    #[param(example = "fix log")]
+    q: Option<String>,
+    /// Page size, from 1 to 100
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    limit: Option<i64>,
+    /// Tasks to skip; cannot be combined with `cursor`
+    #[param(minimum = 0, default = 0)]
+    offset: Option<i64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortField {
+    TaskId,
+    Name,
+    Priority,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct TaskSort {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+#[derive(Default)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    pub priority: Option<i32>,
+    pub priority_min: Option<i32>,
+    pub priority_max: Option<i32>,
+    pub name_contains: Option<String>,
+    /// Inclusive lower bound of `due_at`.
+    pub due_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of `due_at`.
+    pub due_before: Option<DateTime<Utc>>,
+    pub overdue: Option<bool>,
+    /// Lowercase tag names, of which a task needs at least one.
+    pub tags_any: Vec<String>,
+    /// Lowercase tag names, all of which a task needs.
+    pub tags_all: Vec<String>,
+    /// Only the direct subtasks of this task.
+    pub parent_task_id: Option<i32>,
+    /// Lists the trash, i.e. soft-deleted tasks, instead of the live ones.
+    pub deleted: bool,
+}
+
+/// Position after the last task of a page, in terms of the sort key and `task_id` tiebreaker.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(rename = "s")]
+    sort: String,
+    #[serde(rename = "v")]
+    pub value: CursorValue,
+    #[serde(rename = "id")]
+    pub task_id: i32,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CursorValue {
+    Int(i32),
+    Text(String),
+}
+
+pub struct TaskListQuery {
+    pub filter: TaskFilter,
+    pub sort: TaskSort,
+    pub limit: i64,
+    pub offset: i64,
+    pub cursor: Option<Cursor>,
+}
+
+pub struct TaskPage {
+    pub tasks: Vec<TaskRow>,
+    pub total: i64,
+    pub has_more: bool,
+}
+
+/// Position after the last comment of a page; comments are always listed oldest first.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct CommentCursor {
+    #[serde(rename = "id")]
+    pub comment_id: i32,
+}
+
+pub struct CommentListQuery {
+    pub limit: i64,
+    pub offset: i64,
+    pub cursor: Option<CommentCursor>,
+}
+
+/// Position after the last task of a page of search results, by rank then `task_id`.
+///
+/// Ranks only compare within one search, so the cursor also carries a hash of the terms
+/// and is rejected by any other search.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct SearchCursor {
+    #[serde(rename = "q")]
+    pub terms_hash: u64,
+    #[serde(rename = "r")]
+    pub rank: f32,
+    #[serde(rename = "id")]
+    pub task_id: i32,
+}
+
+pub struct TaskSearchQuery {
+    /// Lowercase words, each of which has to start a word of the name.
+    pub terms: Vec<String>,
+    pub limit: i64,
+    pub offset: i64,
+    pub cursor: Option<SearchCursor>,
+}
+
+/// Live tasks matching a search, the most relevant first.
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    pub total: i64,
+    pub has_more: bool,
+}
+
+pub struct CommentPage {
+    pub comments: Vec<CommentRow>,
+    pub total: i64,
+    pub has_more: bool,
+}
+
+impl TaskSort {
+    fn parse(value: &str) -> Result<Self, String> {
+        let (direction, field) = match value.strip_prefix('-') {
+            Some(field) => (SortDirection::Desc, field),
+            None => (SortDirection::Asc, value),
+        };
+
+        let field = match field {
+            "task_id" => SortField::TaskId,
+            "name" => SortField::Name,
+            "priority" => SortField::Priority,
+            _ => {
+                return Err(format!(
+                    "Cannot sort by '{field}', expected one of task_id, name, priority"
+                ));
+            }
+        };
+
+        Ok(Self { field, direction })
+    }
+
+    fn as_param(&self) -> String {
+        let field = match self.field {
+            SortField::TaskId => "task_id",
+            SortField::Name => "name",
+            SortField::Priority => "priority",
+        };
+
+        match self.direction {
+            SortDirection::Asc => field.to_owned(),
+            SortDirection::Desc => format!("-{field}"),
+        }
+    }
+
+    /// Key used for ordering and cursors. Tasks without priority sort as the highest one,
+    /// matching Postgres' default `NULLS LAST` for ascending order.
+    pub fn key_of(&self, task: &TaskRow) -> CursorValue {
+        match self.field {
+            SortField::TaskId => CursorValue::Int(task.task_id),
+            SortField::Name => CursorValue::Text(task.name.clone()),
+            SortField::Priority => CursorValue::Int(task.priority.unwrap_or(i32::MAX)),
+        }
+    }
+}
+
+impl Default for TaskSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::TaskId,
+            direction: SortDirection::Asc,
+        }
+    }
+}
+
+impl Cursor {
+    pub fn after(task: &TaskRow, sort: &TaskSort) -> Self {
+        Self {
+            sort: sort.as_param(),
+            value: sort.key_of(task),
+            task_id: task.task_id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    fn decode(value: &str, sort: &TaskSort) -> Result<Self, String> {
+        let invalid_cursor = || "Invalid cursor".to_owned();
+
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| invalid_cursor())?;
+        let cursor: Self = serde_json::from_slice(&bytes).map_err(|_| invalid_cursor())?;
+
+        if cursor.sort != sort.as_param() {
+            return Err("The cursor was issued for a different sort".to_owned());
+        }
+
+        Ok(cursor)
+    }
+}
+
+impl CommentCursor {
+    pub fn after(comment: &CommentRow) -> Self {
+        Self {
+            comment_id: comment.comment_id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    fn decode(value: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| "Invalid cursor".to_owned())?;
+
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid cursor".to_owned())
+    }
+}
+
+impl SearchCursor {
+    pub fn after(query: &TaskSearchQuery, hit: &SearchHit) -> Self {
+        Self {
+            terms_hash: terms_hash(&query.terms),
+            rank: hit.rank,
+            task_id: hit.task.task_id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    fn decode(value: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| "Invalid cursor".to_owned())?;
+
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid cursor".to_owned())
+    }
+}
+
+impl TaskSearchQuery {
+    pub fn from_params(params: SearchTasksParams) -> Result<Self, String> {
+        let mut terms: Vec<String> =
+            search::words(params.q.as_deref().unwrap_or_default()).collect();
+        terms.sort();
+        terms.dedup();
+
+        if terms.is_empty() {
+            return Err("q must contain at least one word".to_owned());
+        }
+
+        let (limit, offset) = parse_page(params.limit, params.offset)?;
+
+        let cursor = match params.cursor.as_deref() {
+            Some(_) if params.offset.is_some() => {
+                return Err("cursor and offset cannot be combined".to_owned());
+            }
+            Some(cursor) => {
+                let cursor = SearchCursor::decode(cursor)?;
+                if cursor.terms_hash != terms_hash(&terms) {
+                    return Err("cursor belongs to a search with a different q".to_owned());
+                }
+                Some(cursor)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            terms,
+            limit,
+            offset,
+            cursor,
+        })
+    }
+
+    /// The terms as a Postgres `tsquery` matching names with words starting with each of
+    /// them. Terms are made of letters and digits only, so they need no escaping.
+    pub fn ts_query(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| format!("'{term}':*"))
+            .collect::<Vec<_>>()
+            .join(" & ")
+    }
+}
+
+impl CommentListQuery {
+    pub fn from_params(params: ListCommentsParams) -> Result<Self, String> {
+        let (limit, offset) = parse_page(params.limit, params.offset)?;
+
+        let cursor = match params.cursor.as_deref() {
+            Some(_) if params.offset.is_some() => {
+                return Err("cursor and offset cannot be combined".to_owned());
+            }
+            Some(cursor) => Some(CommentCursor::decode(cursor)?),
+            None => None,
+        };
+
+        Ok(Self {
+            limit,
+            offset,
+            cursor,
+        })
+    }
+}
+
+impl TaskListQuery {
+    pub fn from_params(params: ListTasksParams) -> Result<Self, String> {
+        let sort = match params.sort.as_deref() {
+            Some(sort) => TaskSort::parse(sort)?,
+            None => TaskSort::default(),
+        };
+
+        let (limit, offset) = parse_page(params.limit, params.offset)?;
+
+        let cursor = match params.cursor.as_deref() {
+            Some(_) if params.offset.is_some() => {
+                return Err("cursor and offset cannot be combined".to_owned());
+            }
+            Some(cursor) => Some(Cursor::decode(cursor, &sort)?),
+            None => None,
+        };
+
+        let mut due_after = parse_date_time("due_after", params.due_after.as_deref())?;
+        let mut due_before = parse_date_time("due_before", params.due_before.as_deref())?;
+
+        let tz: Tz = match params.tz.as_deref() {
+            Some(tz) => tz
+                .parse()
+                .map_err(|_| format!("tz must be an IANA timezone, got '{tz}'"))?,
+            None => Tz::UTC,
+        };
+
+        if let Some(due_on) = params.due_on.as_deref() {
+            let day = match due_on {
+                "today" => Utc::now().with_timezone(&tz).date_naive(),
+                date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|_| "due_on must be 'today' or a YYYY-MM-DD date".to_owned())?,
+            };
+
+            let (day_start, day_end) = day_bounds(day, tz);
+            due_after = due_after.max(Some(day_start));
+            due_before = Some(due_before.map_or(day_end, |before| before.min(day_end)));
+        }
+
+        let filter = TaskFilter {
+            status: params.status,
+            priority: params.priority,
+            priority_min: params.priority_min,
+            priority_max: params.priority_max,
+            name_contains: params.name_contains.filter(|name| !name.is_empty()),
+            due_after,
+            due_before,
+            overdue: params.overdue,
+            tags_any: parse_tag_names(params.tags_any.as_deref()),
+            tags_all: parse_tag_names(params.tags_all.as_deref()),
+            parent_task_id: None,
+            deleted: false,
+        };
+
+        Ok(Self {
+            filter,
+            sort,
+            limit,
+            offset,
+            cursor,
+        })
+    }
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &TaskRow) -> bool {
+        let deleted_matches = task.deleted_at.is_some() == self.deleted;
+        let status_matches = self.status.is_none_or(|status| task.status == status);
+        let parent_matches = self
+            .parent_task_id
+            .is_none_or(|parent_task_id| task.parent_task_id == Some(parent_task_id));
+        let priority_matches = self.priority.is_none_or(|p| task.priority == Some(p));
+        let min_matches = self
+            .priority_min
+            .is_none_or(|min| task.priority.is_some_and(|p| p >= min));
+        let max_matches = self
+            .priority_max
+            .is_none_or(|max| task.priority.is_some_and(|p| p <= max));
+        let name_matches = self
+            .name_contains
+            .as_ref()
+            .is_none_or(|name| task.name.to_lowercase().contains(&name.to_lowercase()));
+
+        let due_after_matches = self
+            .due_after
+            .is_none_or(|after| task.due_at.is_some_and(|due_at| due_at >= after));
+        let due_before_matches = self
+            .due_before
+            .is_none_or(|before| task.due_at.is_some_and(|due_at| due_at < before));
+        let overdue_matches = self
+            .overdue
+            .is_none_or(|overdue| task.is_overdue(Utc::now()) == overdue);
+
+        let tag_names: Vec<String> = task
+            .tags
+            .iter()
+            .map(|tag| tag.name.to_lowercase())
+            .collect();
+        let tags_any_matches =
+            self.tags_any.is_empty() || self.tags_any.iter().any(|name| tag_names.contains(name));
+        let tags_all_matches = self.tags_all.iter().all(|name| tag_names.contains(name));
+
+        deleted_matches
+            && status_matches
+            && parent_matches
+            && priority_matches
+            && min_matches
+            && max_matches
+            && name_matches
+            && due_after_matches
+            && due_before_matches
+            && overdue_matches
+            && tags_any_matches
+            && tags_all_matches
+    }
+}
+
+/// 64-bit FNV-1a of the search terms, which unlike the standard library's hasher stays the
+/// same across builds, so cursors outlive a redeploy.
+fn terms_hash(terms: &[String]) -> u64 {
+    terms
+        .join(" ")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Validated `limit` and `offset`, with their defaults.
+fn parse_page(limit: Option<i64>, offset: Option<i64>) -> Result<(i64, i64), String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {MAX_LIMIT}"));
+    }
+
+    let offset = offset.unwrap_or(0);
+    if offset < 0 {
+        return Err("offset cannot be negative".to_owned());
+    }
+
+    Ok((limit, offset))
+}
+
+fn parse_date_time(param: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|date_time| date_time.with_timezone(&Utc))
+                .map_err(|_| format!("{param} must be an RFC 3339 date-time, got '{value}'"))
+        })
+        .transpose()
+}
+
+/// Distinct lowercase names of a comma-separated list, skipping empty entries.
+fn parse_tag_names(value: Option<&str>) -> Vec<String> {
+    let mut names: Vec<String> = value
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The instants `day` starts and ends at in `tz`, which is not always 24 hours apart.
+fn day_bounds(day: NaiveDate, tz: Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start_of = |day: NaiveDate| {
+        let midnight = day.and_hms_opt(0, 0, 0).unwrap();
+
+        // Some timezones skip midnight when moving to daylight saving time; the day then
+        // starts at the first instant after the gap.
+        (0..=2)
+            .find_map(|hours| {
+                tz.from_local_datetime(&(midnight + chrono::Duration::hours(hours)))
+                    .earliest()
+            })
+            .expect("Timezone gaps are shorter than two hours")
+            .with_timezone(&Utc)
+    };
+
+    (start_of(day), start_of(day + Days::new(1)))
+}
@@ -0,0 +1,1179 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    error::Error as StdError,
+    fmt,
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Error, error::DatabaseError, error::ErrorKind};
+
+use super::{
+    ACYCLIC_DEPENDENCY_CONSTRAINT, ACYCLIC_PARENT_CONSTRAINT, PARENT_TASK_CONSTRAINT,
+    RestoreOutcome, TaskRepository,
+};
+use crate::{
+    dependencies::TaskGraph,
+    models::{
+        AttachmentRow, CommentRow, CreateCommentReq, CreateTagReq, CreateTaskReq, CreateTaskRow,
+        DEFAULT_TAG_COLOR, NewAttachment, SearchHit, TagRow, TaskChanges, TaskRow, TaskStatus,
+        UpdateTagReq,
+    },
+    query::{
+        CommentListQuery, CommentPage, SearchPage, SortDirection, TaskListQuery, TaskPage,
+        TaskSearchQuery,
+    },
+    search,
+    state::SubtasksOnDelete,
+};
+
+/// Keeps tasks in process memory, for tests and local demos that run without Postgres.
+#[derive(Default)]
+pub struct InMemoryTaskRepository {
+    state: RwLock<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    last_task_id: i32,
+    tasks: BTreeMap<i32, TaskRow>,
+    last_tag_id: i32,
+    tags: BTreeMap<i32, TagRow>,
+    /// `(task_id, tag_id)` pairs, like the `task_tags` table.
+    task_tags: BTreeSet<(i32, i32)>,
+    /// `(task_id, blocker_task_id)` pairs, like the `task_dependencies` table.
+    dependencies: BTreeSet<(i32, i32)>,
+    last_comment_id: i32,
+    comments: BTreeMap<i32, CommentRow>,
+    last_attachment_id: i32,
+    attachments: BTreeMap<i32, AttachmentRow>,
+}
+
+impl InMemoryTaskRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TaskRepository for InMemoryTaskRepository {
+    async fn list(&self, query: &TaskListQuery) -> Result<TaskPage, Error> {
+        let state = self.state.read().unwrap();
+
+        let mut tasks: Vec<TaskRow> = state
+            .tasks
+            .values()
+            .map(|task| hydrate(&state, task))
+            .filter(|task| query.filter.matches(task))
+            .collect();
+
+        let total = tasks.len() as i64;
+
+        let sort = query.sort;
+        tasks.sort_by(|a, b| {
+            let ordering = (sort.key_of(a), a.task_id).cmp(&(sort.key_of(b), b.task_id));
+
+            match sort.direction {
+                SortDirection::Asc => ordering,
+                SortDirection::Desc => ordering.reverse(),
+            }
+        });
+
+        if let Some(cursor) = &query.cursor {
+            let position = (cursor.value.clone(), cursor.task_id);
+
+            tasks.retain(|task| {
+                let key = (sort.key_of(task), task.task_id);
+
+                match sort.direction {
+                    SortDirection::Asc => key > position,
+                    SortDirection::Desc => key < position,
+                }
+            });
+        }
+
+        let mut tasks: Vec<TaskRow> = tasks
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit as usize + 1)
+            .collect();
+
+        let has_more = tasks.len() as i64 > query.limit;
+        tasks.truncate(query.limit as usize);
+
+        Ok(TaskPage {
+            tasks,
+            total,
+            has_more,
+        })
+    }
+
+    async fn search(&self, query: &TaskSearchQuery) -> Result<SearchPage, Error> {
+        let state = self.state.read().unwrap();
+
+        let mut hits: Vec<SearchHit> = state
+            .tasks
+            .values()
+            .filter(|task| task.deleted_at.is_none())
+            .filter_map(|task| {
+                let rank = search::rank(&task.name, &query.terms)?;
+
+                Some(SearchHit {
+                    task: hydrate(&state, task),
+                    rank,
+                    snippet: search::snippet_html(&search::headline(&task.name, &query.terms)),
+                })
+            })
+            .collect();
+
+        let total = hits.len() as i64;
+
+        hits.sort_by(|a, b| {
+            b.rank
+                .total_cmp(&a.rank)
+                .then(a.task.task_id.cmp(&b.task.task_id))
+        });
+
+        if let Some(cursor) = &query.cursor {
+            hits.retain(|hit| {
+                hit.rank < cursor.rank
+                    || (hit.rank == cursor.rank && hit.task.task_id > cursor.task_id)
+            });
+        }
+
+        let mut hits: Vec<SearchHit> = hits
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit as usize + 1)
+            .collect();
+
+        let has_more = hits.len() as i64 > query.limit;
+        hits.truncate(query.limit as usize);
+
+        Ok(SearchPage {
+            hits,
+            total,
+            has_more,
+        })
+    }
+
+    async fn count(&self) -> Result<i64, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(state
+            .tasks
+            .values()
+            .filter(|task| task.deleted_at.is_none())
+            .count() as i64)
+    }
+
+    async fn find_by_id(&self, task_id: i32) -> Result<Option<TaskRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(state
+            .tasks
+            .get(&task_id)
+            .filter(|task| task.deleted_at.is_none())
+            .map(|task| hydrate(&state, task)))
+    }
+
+    async fn subtree(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error> {
+        let state = self.state.read().unwrap();
+
+        if !is_live(&state, task_id) {
+            return Ok(None);
+        }
+
+        let mut tasks = Vec::new();
+        let mut pending = vec![task_id];
+
+        while let Some(task_id) = pending.pop() {
+            tasks.push(hydrate(&state, &state.tasks[&task_id]));
+
+            // Pushed in reverse so that siblings come out by `task_id`.
+            pending.extend(live_subtask_ids(&state, task_id).into_iter().rev());
+        }
+
+        Ok(Some(tasks))
+    }
+
+    async fn create(&self, task: CreateTaskReq) -> Result<CreateTaskRow, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if let Some(parent_task_id) = task.parent_task_id {
+            ensure_valid_parent(&state, None, parent_task_id)?;
+        }
+
+        state.last_task_id += 1;
+        let task_id = state.last_task_id;
+        let now = Utc::now();
+
+        state.tasks.insert(
+            task_id,
+            TaskRow {
+                task_id,
+                parent_task_id: task.parent_task_id,
+                name: task.name,
+                priority: task.priority,
+                status: TaskStatus::Todo,
+                progress: None,
+                blockers_done: true,
+                comment_count: 0,
+                tags: Vec::new(),
+                due_at: task.due_at,
+                start_at: task.start_at,
+                completed_at: None,
+                created_at: now,
+                updated_at: now,
+                version: 1,
+                deleted_at: None,
+            },
+        );
+
+        touch_ancestors(&mut state, task_id);
+
+        Ok(CreateTaskRow { task_id })
+    }
+
+    async fn update(
+        &self,
+        task_id: i32,
+        changes: TaskChanges,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        let Some(row) = guarded_task_mut(&mut state, task_id, expected_versions) else {
+            return Ok(None);
+        };
+        let old_parent_task_id = row.parent_task_id;
+
+        if let Some(Some(parent_task_id)) = changes.parent_task_id
+            && old_parent_task_id != Some(parent_task_id)
+        {
+            ensure_valid_parent(&state, Some(task_id), parent_task_id)?;
+        }
+
+        if changes.is_empty() {
+            let task = state.tasks[&task_id].clone();
+            return Ok(Some(hydrate(&state, &task)));
+        }
+
+        // Moving the task changes the progress of its old ancestors and of its new ones.
+        let moves = changes
+            .parent_task_id
+            .is_some_and(|parent_task_id| parent_task_id != old_parent_task_id);
+
+        if moves {
+            touch_ancestors(&mut state, task_id);
+        }
+
+        let row = state.tasks.get_mut(&task_id).unwrap();
+
+        if let Some(name) = changes.name {
+            row.name = name;
+        }
+
+        if let Some(priority) = changes.priority {
+            row.priority = priority;
+        }
+
+        if let Some(due_at) = changes.due_at {
+            row.due_at = due_at;
+        }
+
+        if let Some(start_at) = changes.start_at {
+            row.start_at = start_at;
+        }
+
+        if let Some(parent_task_id) = changes.parent_task_id {
+            row.parent_task_id = parent_task_id;
+        }
+
+        touch(row);
+        let task = row.clone();
+
+        if moves {
+            touch_ancestors(&mut state, task_id);
+        }
+
+        Ok(Some(hydrate(&state, &task)))
+    }
+
+    async fn set_status(
+        &self,
+        task_id: i32,
+        status: TaskStatus,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        let Some(row) = guarded_task_mut(&mut state, task_id, expected_versions) else {
+            return Ok(None);
+        };
+
+        row.status = status;
+        row.completed_at = (status == TaskStatus::Done).then(Utc::now);
+        touch(row);
+        let task = row.clone();
+
+        touch_ancestors(&mut state, task_id);
+        touch_dependents(&mut state, &[task_id]);
+
+        Ok(Some(hydrate(&state, &task)))
+    }
+
+    async fn delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if guarded_task_mut(&mut state, task_id, expected_versions).is_none() {
+            return Ok(false);
+        }
+
+        touch_ancestors(&mut state, task_id);
+
+        let deleted_ids = match subtasks {
+            SubtasksOnDelete::Orphan => {
+                detach_subtasks(&mut state, task_id);
+                vec![task_id]
+            }
+            SubtasksOnDelete::Cascade => subtree_ids(&state, task_id),
+        };
+
+        touch_dependents(&mut state, &deleted_ids);
+
+        for task_id in &deleted_ids {
+            state.tasks.remove(task_id);
+        }
+        state
+            .task_tags
+            .retain(|(task_id, _)| !deleted_ids.contains(task_id));
+        state.dependencies.retain(|(task_id, blocker_task_id)| {
+            !deleted_ids.contains(task_id) && !deleted_ids.contains(blocker_task_id)
+        });
+        state
+            .comments
+            .retain(|_, comment| !deleted_ids.contains(&comment.task_id));
+        state
+            .attachments
+            .retain(|_, attachment| !deleted_ids.contains(&attachment.task_id));
+
+        Ok(true)
+    }
+
+    async fn soft_delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if guarded_task_mut(&mut state, task_id, expected_versions).is_none() {
+            return Ok(false);
+        }
+
+        touch_ancestors(&mut state, task_id);
+
+        let trashed_ids = match subtasks {
+            SubtasksOnDelete::Orphan => {
+                detach_subtasks(&mut state, task_id);
+                vec![task_id]
+            }
+            SubtasksOnDelete::Cascade => subtree_ids(&state, task_id),
+        };
+
+        let now = Utc::now();
+        let trashed_ids: Vec<i32> = trashed_ids
+            .into_iter()
+            .filter(|task_id| is_live(&state, *task_id))
+            .collect();
+
+        for task_id in &trashed_ids {
+            let task = state.tasks.get_mut(task_id).unwrap();
+            task.deleted_at = Some(now);
+            touch(task);
+        }
+
+        touch_dependents(&mut state, &trashed_ids);
+
+        Ok(true)
+    }
+
+    async fn restore(&self, task_id: i32) -> Result<RestoreOutcome, Error> {
+        let mut state = self.state.write().unwrap();
+
+        let Some(task) = state
+            .tasks
+            .get(&task_id)
+            .filter(|task| task.deleted_at.is_some())
+        else {
+            return Ok(RestoreOutcome::NotInTrash);
+        };
+
+        if let Some(parent_task_id) = task.parent_task_id
+            && !is_live(&state, parent_task_id)
+        {
+            return Ok(RestoreOutcome::ParentInTrash(parent_task_id));
+        }
+
+        let restored_ids = trashed_together_ids(&state, task_id);
+
+        for task_id in &restored_ids {
+            let task = state.tasks.get_mut(task_id).unwrap();
+            task.deleted_at = None;
+            touch(task);
+        }
+
+        touch_ancestors(&mut state, task_id);
+        touch_dependents(&mut state, &restored_ids);
+
+        Ok(RestoreOutcome::Restored)
+    }
+
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64, Error> {
+        let mut state = self.state.write().unwrap();
+
+        let tasks_before = state.tasks.len();
+        state.tasks.retain(|_, task| {
+            task.deleted_at
+                .is_none_or(|deleted_at| deleted_at >= deleted_before)
+        });
+
+        let MemoryState {
+            tasks,
+            task_tags,
+            dependencies,
+            comments,
+            attachments,
+            ..
+        } = &mut *state;
+        task_tags.retain(|(task_id, _)| tasks.contains_key(task_id));
+        dependencies.retain(|(task_id, blocker_task_id)| {
+            tasks.contains_key(task_id) && tasks.contains_key(blocker_task_id)
+        });
+        comments.retain(|_, comment| tasks.contains_key(&comment.task_id));
+        attachments.retain(|_, attachment| tasks.contains_key(&attachment.task_id));
+
+        // Like the `ON DELETE SET NULL` of the foreign key, without bumping versions.
+        let task_ids: BTreeSet<i32> = tasks.keys().copied().collect();
+        for task in tasks.values_mut() {
+            if task
+                .parent_task_id
+                .is_some_and(|parent_task_id| !task_ids.contains(&parent_task_id))
+            {
+                task.parent_task_id = None;
+            }
+        }
+
+        Ok((tasks_before - state.tasks.len()) as u64)
+    }
+
+    async fn list_tags(&self) -> Result<Vec<TagRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        let mut tags: Vec<TagRow> = state.tags.values().cloned().collect();
+        tags.sort_by_key(|tag| tag.name.to_lowercase());
+
+        Ok(tags)
+    }
+
+    async fn find_tag(&self, tag_id: i32) -> Result<Option<TagRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(state.tags.get(&tag_id).cloned())
+    }
+
+    async fn create_tag(&self, tag: CreateTagReq) -> Result<TagRow, Error> {
+        let mut state = self.state.write().unwrap();
+
+        ensure_unique_tag_name(&state, &tag.name, None)?;
+
+        state.last_tag_id += 1;
+        let tag = TagRow {
+            tag_id: state.last_tag_id,
+            name: tag.name,
+            color: tag
+                .color
+                .as_deref()
+                .unwrap_or(DEFAULT_TAG_COLOR)
+                .to_lowercase(),
+        };
+        state.tags.insert(tag.tag_id, tag.clone());
+
+        Ok(tag)
+    }
+
+    async fn update_tag(
+        &self,
+        tag_id: i32,
+        changes: UpdateTagReq,
+    ) -> Result<Option<TagRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !state.tags.contains_key(&tag_id) {
+            return Ok(None);
+        }
+
+        if let Some(name) = &changes.name {
+            ensure_unique_tag_name(&state, name, Some(tag_id))?;
+        }
+
+        touch_tagged_tasks(&mut state, tag_id);
+
+        let tag = state.tags.get_mut(&tag_id).unwrap();
+
+        if let Some(name) = changes.name {
+            tag.name = name;
+        }
+
+        if let Some(color) = changes.color {
+            tag.color = color.to_lowercase();
+        }
+
+        Ok(Some(tag.clone()))
+    }
+
+    async fn delete_tag(&self, tag_id: i32) -> Result<bool, Error> {
+        let mut state = self.state.write().unwrap();
+
+        touch_tagged_tasks(&mut state, tag_id);
+        state
+            .task_tags
+            .retain(|&(_, tagged_id)| tagged_id != tag_id);
+
+        Ok(state.tags.remove(&tag_id).is_some())
+    }
+
+    async fn merge_tags(&self, tag_id: i32, into_tag_id: i32) -> Result<Option<TagRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !state.tags.contains_key(&tag_id) || !state.tags.contains_key(&into_tag_id) {
+            return Ok(None);
+        }
+
+        touch_tagged_tasks(&mut state, tag_id);
+
+        let merged: Vec<(i32, i32)> = state
+            .task_tags
+            .iter()
+            .filter(|&&(_, tagged_id)| tagged_id == tag_id)
+            .copied()
+            .collect();
+
+        for (task_id, _) in merged {
+            state.task_tags.remove(&(task_id, tag_id));
+            state.task_tags.insert((task_id, into_tag_id));
+        }
+
+        state.tags.remove(&tag_id);
+
+        Ok(state.tags.get(&into_tag_id).cloned())
+    }
+
+    async fn attach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if guarded_task_mut(&mut state, task_id, None).is_none() {
+            return Ok(None);
+        }
+
+        if state.task_tags.insert((task_id, tag_id)) {
+            touch(state.tasks.get_mut(&task_id).unwrap());
+        }
+
+        Ok(Some(hydrate(&state, &state.tasks[&task_id])))
+    }
+
+    async fn detach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if guarded_task_mut(&mut state, task_id, None).is_none() {
+            return Ok(None);
+        }
+
+        if state.task_tags.remove(&(task_id, tag_id)) {
+            touch(state.tasks.get_mut(&task_id).unwrap());
+        }
+
+        Ok(Some(hydrate(&state, &state.tasks[&task_id])))
+    }
+
+    async fn blockers(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error> {
+        let state = self.state.read().unwrap();
+
+        if !is_live(&state, task_id) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            blocker_ids(&state, task_id)
+                .filter(|&blocker_task_id| is_live(&state, blocker_task_id))
+                .map(|blocker_task_id| hydrate(&state, &state.tasks[&blocker_task_id]))
+                .collect(),
+        ))
+    }
+
+    async fn add_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !is_live(&state, task_id) || !is_live(&state, blocker_task_id) {
+            return Ok(None);
+        }
+
+        // Like the `check_task_dependency` trigger: the blocker must not depend on the task.
+        let mut pending = vec![blocker_task_id];
+        let mut visited = BTreeSet::new();
+
+        while let Some(id) = pending.pop() {
+            if id == task_id {
+                return Err(constraint_violation(
+                    CHECK_VIOLATION,
+                    ACYCLIC_DEPENDENCY_CONSTRAINT,
+                ));
+            }
+
+            if visited.insert(id) {
+                pending.extend(blocker_ids(&state, id));
+            }
+        }
+
+        if state.dependencies.insert((task_id, blocker_task_id)) {
+            touch(state.tasks.get_mut(&task_id).unwrap());
+        }
+
+        Ok(Some(hydrate(&state, &state.tasks[&task_id])))
+    }
+
+    async fn remove_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !is_live(&state, task_id) {
+            return Ok(None);
+        }
+
+        if state.dependencies.remove(&(task_id, blocker_task_id)) {
+            touch(state.tasks.get_mut(&task_id).unwrap());
+        }
+
+        Ok(Some(hydrate(&state, &state.tasks[&task_id])))
+    }
+
+    async fn open_task_graph(&self) -> Result<TaskGraph, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(TaskGraph {
+            tasks: state
+                .tasks
+                .values()
+                .filter(|task| task.deleted_at.is_none() && !task.status.is_finished())
+                .map(|task| hydrate(&state, task))
+                .collect(),
+            dependencies: state.dependencies.iter().copied().collect(),
+        })
+    }
+
+    async fn list_comments(
+        &self,
+        task_id: i32,
+        query: &CommentListQuery,
+    ) -> Result<CommentPage, Error> {
+        let state = self.state.read().unwrap();
+
+        let comments: Vec<&CommentRow> = state
+            .comments
+            .values()
+            .filter(|comment| comment.task_id == task_id)
+            .collect();
+
+        let total = comments.len() as i64;
+
+        let mut comments: Vec<CommentRow> = comments
+            .into_iter()
+            .filter(|comment| {
+                query
+                    .cursor
+                    .is_none_or(|cursor| comment.comment_id > cursor.comment_id)
+            })
+            .skip(query.offset as usize)
+            .take(query.limit as usize + 1)
+            .cloned()
+            .collect();
+
+        let has_more = comments.len() as i64 > query.limit;
+        comments.truncate(query.limit as usize);
+
+        Ok(CommentPage {
+            comments,
+            total,
+            has_more,
+        })
+    }
+
+    async fn find_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+    ) -> Result<Option<CommentRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(comment_of_live_task(&state, task_id, comment_id).cloned())
+    }
+
+    async fn create_comment(
+        &self,
+        task_id: i32,
+        comment: CreateCommentReq,
+    ) -> Result<Option<CommentRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !is_live(&state, task_id) {
+            return Ok(None);
+        }
+
+        state.last_comment_id += 1;
+        let comment_id = state.last_comment_id;
+        let now = Utc::now();
+
+        let comment = CommentRow {
+            comment_id,
+            task_id,
+            author: comment.author,
+            body: comment.body,
+            body_html: String::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.comments.insert(comment_id, comment.clone());
+        touch(state.tasks.get_mut(&task_id).unwrap());
+
+        Ok(Some(comment))
+    }
+
+    async fn update_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+        body: String,
+    ) -> Result<Option<CommentRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if comment_of_live_task(&state, task_id, comment_id).is_none() {
+            return Ok(None);
+        }
+
+        let comment = state.comments.get_mut(&comment_id).unwrap();
+        comment.body = body;
+        comment.updated_at = Utc::now();
+
+        Ok(Some(comment.clone()))
+    }
+
+    async fn delete_comment(&self, task_id: i32, comment_id: i32) -> Result<bool, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if comment_of_live_task(&state, task_id, comment_id).is_none() {
+            return Ok(false);
+        }
+
+        state.comments.remove(&comment_id);
+        touch(state.tasks.get_mut(&task_id).unwrap());
+
+        Ok(true)
+    }
+
+    async fn list_attachments(&self, task_id: i32) -> Result<Vec<AttachmentRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(state
+            .attachments
+            .values()
+            .filter(|attachment| attachment.task_id == task_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(attachment_of_live_task(&state, task_id, attachment_id).cloned())
+    }
+
+    async fn create_attachment(
+        &self,
+        attachment: NewAttachment,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if !is_live(&state, attachment.task_id) {
+            return Ok(None);
+        }
+
+        state.last_attachment_id += 1;
+        let attachment_id = state.last_attachment_id;
+
+        let attachment = AttachmentRow {
+            attachment_id,
+            task_id: attachment.task_id,
+            file_name: attachment.file_name,
+            content_type: attachment.content_type,
+            size: attachment.size,
+            storage_key: attachment.storage_key,
+            created_at: Utc::now(),
+        };
+        state.attachments.insert(attachment_id, attachment.clone());
+
+        Ok(Some(attachment))
+    }
+
+    async fn delete_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let mut state = self.state.write().unwrap();
+
+        if attachment_of_live_task(&state, task_id, attachment_id).is_none() {
+            return Ok(None);
+        }
+
+        Ok(state.attachments.remove(&attachment_id))
+    }
+
+    async fn attachment_storage_keys(&self) -> Result<Vec<String>, Error> {
+        let state = self.state.read().unwrap();
+
+        Ok(state
+            .attachments
+            .values()
+            .map(|attachment| attachment.storage_key.clone())
+            .collect())
+    }
+}
+
+/// Records a write: bumps the version and stamps `updated_at`.
+fn touch(task: &mut TaskRow) {
+    task.version += 1;
+    task.updated_at = Utc::now();
+}
+
+/// Finds a live task whose version is one of `expected_versions`, when given.
+fn guarded_task_mut<'a>(
+    state: &'a mut MemoryState,
+    task_id: i32,
+    expected_versions: Option<&[i32]>,
+) -> Option<&'a mut TaskRow> {
+    state.tasks.get_mut(&task_id).filter(|task| {
+        task.deleted_at.is_none()
+            && expected_versions.is_none_or(|versions| versions.contains(&task.version))
+    })
+}
+
+/// A copy of `task` with its tags, sorted by name like the Postgres repository does, the
+/// progress of its subtasks, the state of its blockers and its number of comments.
+fn hydrate(state: &MemoryState, task: &TaskRow) -> TaskRow {
+    let mut tags: Vec<TagRow> = state
+        .task_tags
+        .range((task.task_id, i32::MIN)..=(task.task_id, i32::MAX))
+        .filter_map(|(_, tag_id)| state.tags.get(tag_id).cloned())
+        .collect();
+    tags.sort_by_key(|tag| tag.name.to_lowercase());
+
+    let mut descendants = Vec::new();
+    let mut pending = live_subtask_ids(state, task.task_id);
+
+    while let Some(task_id) = pending.pop() {
+        descendants.push(state.tasks[&task_id].status);
+        pending.extend(live_subtask_ids(state, task_id));
+    }
+
+    descendants.retain(|&status| status != TaskStatus::Cancelled);
+    let done = descendants
+        .iter()
+        .filter(|&&status| status == TaskStatus::Done)
+        .count();
+    let progress = (!descendants.is_empty()).then(|| (100 * done / descendants.len()) as i32);
+
+    let blockers_done = blocker_ids(state, task.task_id)
+        .filter_map(|blocker_task_id| state.tasks.get(&blocker_task_id))
+        .all(|blocker| blocker.deleted_at.is_some() || blocker.status == TaskStatus::Done);
+
+    let comment_count = state
+        .comments
+        .values()
+        .filter(|comment| comment.task_id == task.task_id)
+        .count() as i64;
+
+    TaskRow {
+        tags,
+        progress,
+        blockers_done,
+        comment_count,
+        ..task.clone()
+    }
+}
+
+/// A comment of `task_id`, provided that task is live.
+fn comment_of_live_task(state: &MemoryState, task_id: i32, comment_id: i32) -> Option<&CommentRow> {
+    state
+        .comments
+        .get(&comment_id)
+        .filter(|comment| comment.task_id == task_id && is_live(state, task_id))
+}
+
+/// An attachment of `task_id`, provided that task is live.
+fn attachment_of_live_task(
+    state: &MemoryState,
+    task_id: i32,
+    attachment_id: i32,
+) -> Option<&AttachmentRow> {
+    state
+        .attachments
+        .get(&attachment_id)
+        .filter(|attachment| attachment.task_id == task_id && is_live(state, task_id))
+}
+
+/// IDs of the tasks blocking a task, trashed or not.
+fn blocker_ids(state: &MemoryState, task_id: i32) -> impl Iterator<Item = i32> + '_ {
+    state
+        .dependencies
+        .range((task_id, i32::MIN)..=(task_id, i32::MAX))
+        .map(|&(_, blocker_task_id)| blocker_task_id)
+}
+
+fn is_live(state: &MemoryState, task_id: i32) -> bool {
+    state
+        .tasks
+        .get(&task_id)
+        .is_some_and(|task| task.deleted_at.is_none())
+}
+
+/// IDs of the direct live subtasks of a task, in order.
+fn live_subtask_ids(state: &MemoryState, task_id: i32) -> Vec<i32> {
+    state
+        .tasks
+        .values()
+        .filter(|task| task.parent_task_id == Some(task_id) && task.deleted_at.is_none())
+        .map(|task| task.task_id)
+        .collect()
+}
+
+/// IDs of a task and every task below it, trashed or not.
+fn subtree_ids(state: &MemoryState, task_id: i32) -> Vec<i32> {
+    let mut subtree_ids = vec![task_id];
+    let mut position = 0;
+
+    while let Some(&task_id) = subtree_ids.get(position) {
+        subtree_ids.extend(
+            state
+                .tasks
+                .values()
+                .filter(|task| task.parent_task_id == Some(task_id))
+                .map(|task| task.task_id),
+        );
+        position += 1;
+    }
+
+    subtree_ids
+}
+
+/// IDs of a trashed task and of the subtasks trashed along with it: a cascading trash
+/// stamps the whole subtree with the same `deleted_at`.
+fn trashed_together_ids(state: &MemoryState, task_id: i32) -> Vec<i32> {
+    let deleted_at = state.tasks[&task_id].deleted_at;
+    let mut trashed_ids = vec![task_id];
+    let mut position = 0;
+
+    while let Some(&task_id) = trashed_ids.get(position) {
+        trashed_ids.extend(
+            state
+                .tasks
+                .values()
+                .filter(|task| {
+                    task.parent_task_id == Some(task_id) && task.deleted_at == deleted_at
+                })
+                .map(|task| task.task_id),
+        );
+        position += 1;
+    }
+
+    trashed_ids
+}
+
+/// Turns the direct subtasks of a task, trashed or not, into top-level tasks.
+fn detach_subtasks(state: &mut MemoryState, task_id: i32) {
+    for task in state.tasks.values_mut() {
+        if task.parent_task_id == Some(task_id) {
+            task.parent_task_id = None;
+            touch(task);
+        }
+    }
+}
+
+/// Fails like the `check_task_parent` trigger unless `parent_task_id` is live and is neither
+/// `task_id` nor below it.
+fn ensure_valid_parent(
+    state: &MemoryState,
+    task_id: Option<i32>,
+    parent_task_id: i32,
+) -> Result<(), Error> {
+    if !is_live(state, parent_task_id) {
+        return Err(constraint_violation(
+            FOREIGN_KEY_VIOLATION,
+            PARENT_TASK_CONSTRAINT,
+        ));
+    }
+
+    let mut ancestor_id = Some(parent_task_id);
+
+    while let Some(id) = ancestor_id {
+        if Some(id) == task_id {
+            return Err(constraint_violation(
+                CHECK_VIOLATION,
+                ACYCLIC_PARENT_CONSTRAINT,
+            ));
+        }
+
+        ancestor_id = state.tasks.get(&id).and_then(|task| task.parent_task_id);
+    }
+
+    Ok(())
+}
+
+/// Records a change of the `progress` of every task above `task_id`.
+fn touch_ancestors(state: &mut MemoryState, task_id: i32) {
+    let mut ancestor_id = state.tasks[&task_id].parent_task_id;
+
+    while let Some(task) = ancestor_id.and_then(|id| state.tasks.get_mut(&id)) {
+        touch(task);
+        ancestor_id = task.parent_task_id;
+    }
+}
+
+/// Records a change of `blockers_done` of every task blocked by one of `blocker_task_ids`.
+fn touch_dependents(state: &mut MemoryState, blocker_task_ids: &[i32]) {
+    let dependent_ids: BTreeSet<i32> = state
+        .dependencies
+        .iter()
+        .filter(|(_, blocker_task_id)| blocker_task_ids.contains(blocker_task_id))
+        .map(|&(task_id, _)| task_id)
+        .collect();
+
+    for task_id in dependent_ids {
+        if let Some(task) = state.tasks.get_mut(&task_id) {
+            touch(task);
+        }
+    }
+}
+
+/// Records a change of the tag embedded in every task it is attached to.
+fn touch_tagged_tasks(state: &mut MemoryState, tag_id: i32) {
+    let MemoryState {
+        tasks, task_tags, ..
+    } = state;
+
+    for (task_id, _) in task_tags
+        .iter()
+        .filter(|&&(_, tagged_id)| tagged_id == tag_id)
+    {
+        if let Some(task) = tasks.get_mut(task_id) {
+            touch(task);
+        }
+    }
+}
+
+/// Fails like the `tags_name_key` unique index when another tag has the same name.
+fn ensure_unique_tag_name(
+    state: &MemoryState,
+    name: &str,
+    tag_id: Option<i32>,
+) -> Result<(), Error> {
+    let taken = state
+        .tags
+        .values()
+        .any(|tag| Some(tag.tag_id) != tag_id && tag.name.to_lowercase() == name.to_lowercase());
+
+    if taken {
+        return Err(constraint_violation(UNIQUE_VIOLATION, "tags_name_key"));
+    }
+
+    Ok(())
+}
+
+/// SQLSTATE codes of the constraint violations the repository emulates.
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const CHECK_VIOLATION: &str = "23514";
+
+fn constraint_violation(code: &'static str, constraint: &'static str) -> Error {
+    Error::Database(Box::new(ConstraintViolation { code, constraint }))
+}
+
+/// Stand-in for the error Postgres reports when a constraint rejects a write.
+#[derive(Debug)]
+struct ConstraintViolation {
+    code: &'static str,
+    constraint: &'static str,
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "violates constraint \"{}\"", self.constraint)
+    }
+}
+
+impl StdError for ConstraintViolation {}
+
+impl DatabaseError for ConstraintViolation {
+    fn message(&self) -> &str {
+        "violates constraint"
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.code))
+    }
+
+    fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        Some(self.constraint)
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self.code {
+            UNIQUE_VIOLATION => ErrorKind::UniqueViolation,
+            FOREIGN_KEY_VIOLATION => ErrorKind::ForeignKeyViolation,
+            CHECK_VIOLATION => ErrorKind::CheckViolation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
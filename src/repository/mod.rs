@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::Error;
+
+use crate::{
+    dependencies::TaskGraph,
+    models::{
+        AttachmentRow, CommentRow, CreateCommentReq, CreateTagReq, CreateTaskReq, CreateTaskRow,
+        NewAttachment, TagRow, TaskChanges, TaskRow, TaskStatus, UpdateTagReq,
+    },
+    query::{CommentListQuery, CommentPage, SearchPage, TaskListQuery, TaskPage, TaskSearchQuery},
+    state::SubtasksOnDelete,
+};
+
+mod memory;
+mod postgres;
+
+pub use memory::InMemoryTaskRepository;
+pub use postgres::PgTaskRepository;
+
+/// Foreign key violated by a parent that is missing or in the trash.
+pub const PARENT_TASK_CONSTRAINT: &str = "tasks_parent_task_id_fkey";
+
+/// Check violated by a parent that is the task itself or one of its subtasks.
+pub const ACYCLIC_PARENT_CONSTRAINT: &str = "tasks_parent_task_id_acyclic";
+
+/// Check violated by a dependency that would make a task end up blocking itself.
+pub const ACYCLIC_DEPENDENCY_CONSTRAINT: &str = "task_dependencies_acyclic";
+
+/// What [`TaskRepository::restore`] did.
+pub enum RestoreOutcome {
+    Restored,
+    NotInTrash,
+    /// The task is a subtask of this task, which is in the trash too.
+    ParentInTrash(i32),
+}
+
+/// Owns every read and write of tasks, so handlers never touch the storage directly.
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn list(&self, query: &TaskListQuery) -> Result<TaskPage, Error>;
+
+    /// A page of the live tasks whose name matches a search, the most relevant first then
+    /// by `task_id`.
+    async fn search(&self, query: &TaskSearchQuery) -> Result<SearchPage, Error>;
+
+    /// Counts the live tasks, leaving out the trash.
+    async fn count(&self) -> Result<i64, Error>;
+
+    /// Finds a live task; tasks in the trash are treated as missing.
+    async fn find_by_id(&self, task_id: i32) -> Result<Option<TaskRow>, Error>;
+
+    /// A live task followed by its live subtasks at any depth, depth first with siblings
+    /// by `task_id`. Returns `None` when there is no such live task.
+    async fn subtree(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error>;
+
+    /// Fails with a violation of [`PARENT_TASK_CONSTRAINT`] when the parent is not live.
+    async fn create(&self, task: CreateTaskReq) -> Result<CreateTaskRow, Error>;
+
+    /// Applies `changes` to a live task in a single step, bumping its version, and returns
+    /// the updated task; without any change, the task is returned as it is. Returns `None`
+    /// when there is no such task or, with `expected_versions`, when the stored version is
+    /// not one of them. A new parent has to be live and cannot create a cycle, see
+    /// [`ACYCLIC_PARENT_CONSTRAINT`].
+    async fn update(
+        &self,
+        task_id: i32,
+        changes: TaskChanges,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error>;
+
+    /// Moves a live task to `status`, bumping its version, and returns the updated task.
+    /// `completed_at` is stamped when the task becomes `done` and cleared otherwise.
+    /// Returns `None` like [`TaskRepository::update`].
+    async fn set_status(
+        &self,
+        task_id: i32,
+        status: TaskStatus,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error>;
+
+    /// Permanently removes a live task, returning whether it was removed. Its subtasks are
+    /// either removed too or detached, bumping their version.
+    async fn delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error>;
+
+    /// Moves a live task to the trash, returning whether it was moved. Its live subtasks
+    /// are either moved along with it or detached, bumping their version.
+    async fn soft_delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error>;
+
+    /// Brings a task back from the trash along with the subtasks trashed with it, bumping
+    /// their version. A subtask of a task still in the trash stays there.
+    async fn restore(&self, task_id: i32) -> Result<RestoreOutcome, Error>;
+
+    /// Permanently removes tasks moved to the trash before `deleted_before`,
+    /// returning how many were removed.
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64, Error>;
+
+    /// Every tag, ordered by name.
+    async fn list_tags(&self) -> Result<Vec<TagRow>, Error>;
+
+    async fn find_tag(&self, tag_id: i32) -> Result<Option<TagRow>, Error>;
+
+    /// Fails with a unique violation when another tag has the same name, ignoring case.
+    async fn create_tag(&self, tag: CreateTagReq) -> Result<TagRow, Error>;
+
+    /// Renames and/or recolors a tag, bumping the version of every task it is attached to.
+    /// Returns `None` when there is no such tag.
+    async fn update_tag(&self, tag_id: i32, changes: UpdateTagReq)
+    -> Result<Option<TagRow>, Error>;
+
+    /// Deletes a tag and detaches it from its tasks, returning whether it existed.
+    async fn delete_tag(&self, tag_id: i32) -> Result<bool, Error>;
+
+    /// Attaches the tasks of `tag_id` to `into_tag_id` as well, then deletes `tag_id`.
+    /// Returns the remaining tag, or `None` when either tag is missing.
+    async fn merge_tags(&self, tag_id: i32, into_tag_id: i32) -> Result<Option<TagRow>, Error>;
+
+    /// Attaches an existing tag to a live task, bumping the task's version unless it was
+    /// already attached. Returns `None` when there is no such live task.
+    async fn attach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error>;
+
+    /// Detaches a tag from a live task, bumping the task's version if it was attached.
+    /// Returns `None` when there is no such live task.
+    async fn detach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error>;
+
+    /// The live tasks blocking a live task, by `task_id`. Returns `None` when there is no
+    /// such live task.
+    async fn blockers(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error>;
+
+    /// Records that a live task is blocked by another live one, bumping the task's version
+    /// unless it already was. Returns `None` when either task is missing, and fails with a
+    /// violation of [`ACYCLIC_DEPENDENCY_CONSTRAINT`] when the blocker depends on the task.
+    async fn add_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error>;
+
+    /// Removes a dependency of a live task, bumping the task's version if it had it.
+    /// Returns `None` when there is no such live task.
+    async fn remove_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error>;
+
+    /// Every live task that is neither `done` nor `cancelled`, with the dependencies
+    /// between them.
+    async fn open_task_graph(&self) -> Result<TaskGraph, Error>;
+
+    /// A page of the comments of a task, oldest first.
+    async fn list_comments(
+        &self,
+        task_id: i32,
+        query: &CommentListQuery,
+    ) -> Result<CommentPage, Error>;
+
+    /// Finds a comment of a live task.
+    async fn find_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+    ) -> Result<Option<CommentRow>, Error>;
+
+    /// Adds a comment to a live task, bumping the task's version since it embeds its
+    /// `comment_count`. Returns `None` when there is no such live task.
+    async fn create_comment(
+        &self,
+        task_id: i32,
+        comment: CreateCommentReq,
+    ) -> Result<Option<CommentRow>, Error>;
+
+    /// Replaces the body of a comment of a live task, stamping `updated_at`. Returns
+    /// `None` when there is no such comment.
+    async fn update_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+        body: String,
+    ) -> Result<Option<CommentRow>, Error>;
+
+    /// Deletes a comment of a live task, bumping the task's version, and returns whether
+    /// it existed.
+    async fn delete_comment(&self, task_id: i32, comment_id: i32) -> Result<bool, Error>;
+
+    /// The attachments of a task, oldest first.
+    async fn list_attachments(&self, task_id: i32) -> Result<Vec<AttachmentRow>, Error>;
+
+    /// Finds an attachment of a live task.
+    async fn find_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error>;
+
+    /// Records an attachment of a live task, which keeps its version. Returns `None` when
+    /// there is no such live task.
+    async fn create_attachment(
+        &self,
+        attachment: NewAttachment,
+    ) -> Result<Option<AttachmentRow>, Error>;
+
+    /// Deletes an attachment of a live task, returning it so its file can be deleted too.
+    async fn delete_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error>;
+
+    /// Storage keys of every attachment, including those of trashed tasks.
+    async fn attachment_storage_keys(&self) -> Result<Vec<String>, Error>;
+}
+
+pub type SharedTaskRepository = Arc<dyn TaskRepository>;
@@ -0,0 +1,1271 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use prometheus::IntGauge;
+use sqlx::{
+    Connection, Error, FromRow, PgConnection, Pool, Postgres, QueryBuilder, pool::PoolConnection,
+};
+
+use super::{RestoreOutcome, TaskRepository};
+use crate::{
+    dependencies::TaskGraph,
+    metrics::Metrics,
+    models::{
+        AttachmentRow, CommentRow, CreateCommentReq, CreateTagReq, CreateTaskReq, CreateTaskRow,
+        DEFAULT_TAG_COLOR, NewAttachment, SearchHit, TagRow, TaskChanges, TaskRow, TaskStatus,
+        UpdateTagReq,
+    },
+    query::{
+        CommentListQuery, CommentPage, CursorValue, SearchPage, SortDirection, SortField,
+        TaskFilter, TaskListQuery, TaskPage, TaskSearchQuery, TaskSort,
+    },
+    search::{self, MATCH_END, MATCH_START},
+    state::SubtasksOnDelete,
+};
+
+/// Columns of a [`TaskRow`], with its tags aggregated as JSON, the progress of its live
+/// subtasks rolled up, the state of its blockers and its number of comments.
+const TASK_COLUMNS: &str = "tasks.*, COALESCE(( \
+    SELECT json_agg(tags ORDER BY LOWER(tags.name)) \
+    FROM task_tags JOIN tags USING (tag_id) \
+    WHERE task_tags.task_id = tasks.task_id \
+), '[]') AS tags, ( \
+    WITH RECURSIVE descendants AS ( \
+        SELECT child.task_id, child.status FROM tasks AS child \
+        WHERE child.parent_task_id = tasks.task_id AND child.deleted_at IS NULL \
+        UNION ALL \
+        SELECT child.task_id, child.status FROM tasks AS child \
+        JOIN descendants ON child.parent_task_id = descendants.task_id \
+        WHERE child.deleted_at IS NULL \
+    ) \
+    SELECT (100 * COUNT(*) FILTER (WHERE status = 'done') / NULLIF(COUNT(*), 0))::INT \
+    FROM descendants WHERE status <> 'cancelled' \
+) AS progress, NOT EXISTS ( \
+    SELECT 1 FROM task_dependencies JOIN tasks AS blocker \
+    ON blocker.task_id = task_dependencies.blocker_task_id \
+    WHERE task_dependencies.task_id = tasks.task_id \
+    AND blocker.deleted_at IS NULL AND blocker.status <> 'done' \
+) AS blockers_done, ( \
+    SELECT COUNT(*) FROM comments WHERE comments.task_id = tasks.task_id \
+) AS comment_count";
+
+/// Searched document of a task; the same expression as `tasks_name_search_idx`, so the
+/// index serves the searches.
+const SEARCH_DOCUMENT: &str = "to_tsvector('simple', tasks.name)";
+
+/// Restricts a query of `comments` to the comments of a live task.
+const OF_LIVE_TASK: &str = "EXISTS ( \
+    SELECT 1 FROM tasks WHERE tasks.task_id = comments.task_id AND tasks.deleted_at IS NULL \
+)";
+
+/// Restricts a query of `attachments` to the attachments of a live task.
+const ATTACHED_TO_LIVE_TASK: &str = "EXISTS ( \
+    SELECT 1 FROM tasks WHERE tasks.task_id = attachments.task_id AND tasks.deleted_at IS NULL \
+)";
+
+const TRASH_TASKS: &str =
+    "UPDATE tasks SET deleted_at = NOW(), version = version + 1, updated_at = NOW()";
+
+/// `subtree` holds the ID of task `$1` and of every task below it, trashed or not.
+const SUBTREE: &str = "WITH RECURSIVE subtree AS ( \
+    SELECT task_id FROM tasks WHERE task_id = $1 \
+    UNION \
+    SELECT tasks.task_id FROM tasks JOIN subtree ON tasks.parent_task_id = subtree.task_id \
+)";
+
+pub struct PgTaskRepository {
+    pg_pool: Pool<Postgres>,
+    /// Only set once the repository reports to [`Metrics`].
+    waiting_acquires: Option<IntGauge>,
+}
+
+impl PgTaskRepository {
+    pub fn new(pg_pool: Pool<Postgres>) -> Self {
+        Self {
+            pg_pool,
+            waiting_acquires: None,
+        }
+    }
+
+    /// Reports the queries waiting for a pooled connection to `metrics`.
+    pub fn with_metrics(mut self, metrics: &Metrics) -> Self {
+        self.waiting_acquires = Some(metrics.pool_waiting());
+        self
+    }
+
+    /// Checks a connection out of the pool, counting the wait in `waiting_acquires`.
+    async fn acquire(&self) -> Result<PoolConnection<Postgres>, Error> {
+        let _waiting = self.waiting_acquires.as_ref().map(WaitingAcquire::start);
+
+        self.pg_pool.acquire().await
+    }
+}
+
+/// A task matching a search, with its name highlighted by `ts_headline`.
+#[derive(FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    task: TaskRow,
+    rank: f32,
+    headline: String,
+}
+
+/// Holds `waiting_acquires` up while a connection is awaited, including when the
+/// request is cancelled half-way.
+struct WaitingAcquire<'a>(&'a IntGauge);
+
+impl<'a> WaitingAcquire<'a> {
+    fn start(gauge: &'a IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for WaitingAcquire<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[async_trait]
+impl TaskRepository for PgTaskRepository {
+    async fn list(&self, query: &TaskListQuery) -> Result<TaskPage, Error> {
+        let mut connection = self.acquire().await?;
+
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM tasks WHERE TRUE");
+        push_filter(&mut count_query, &query.filter);
+
+        let total: i64 = count_query
+            .build_query_scalar()
+            .fetch_one(&mut *connection)
+            .await?;
+
+        let mut page_query =
+            QueryBuilder::new(format!("SELECT {TASK_COLUMNS} FROM tasks WHERE TRUE"));
+        push_filter(&mut page_query, &query.filter);
+
+        let sort_expression = sort_expression(&query.sort);
+        let (comparison, direction) = match query.sort.direction {
+            SortDirection::Asc => (">", "ASC"),
+            SortDirection::Desc => ("<", "DESC"),
+        };
+
+        if let Some(cursor) = &query.cursor {
+            page_query.push(format!(" AND ({sort_expression}, task_id) {comparison} ("));
+            match &cursor.value {
+                CursorValue::Int(value) => page_query.push_bind(*value),
+                CursorValue::Text(value) => page_query.push_bind(value.clone()),
+            };
+            page_query.push(", ").push_bind(cursor.task_id).push(")");
+        }
+
+        page_query
+            .push(format!(
+                " ORDER BY {sort_expression} {direction}, task_id {direction} LIMIT "
+            ))
+            .push_bind(query.limit + 1)
+            .push(" OFFSET ")
+            .push_bind(query.offset);
+
+        let mut tasks: Vec<TaskRow> = page_query
+            .build_query_as()
+            .fetch_all(&mut *connection)
+            .await?;
+
+        let has_more = tasks.len() as i64 > query.limit;
+        tasks.truncate(query.limit as usize);
+
+        Ok(TaskPage {
+            tasks,
+            total,
+            has_more,
+        })
+    }
+
+    async fn search(&self, query: &TaskSearchQuery) -> Result<SearchPage, Error> {
+        let mut connection = self.acquire().await?;
+        let ts_query = query.ts_query();
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM tasks \
+             WHERE deleted_at IS NULL AND {SEARCH_DOCUMENT} @@ to_tsquery('simple', $1)"
+        ))
+        .bind(&ts_query)
+        .fetch_one(&mut *connection)
+        .await?;
+
+        let rank = format!("ts_rank({SEARCH_DOCUMENT}, search_query)");
+
+        let mut page_query = QueryBuilder::new(format!(
+            "SELECT {TASK_COLUMNS}, {rank} AS rank, \
+             ts_headline('simple', tasks.name, search_query, \
+             'StartSel={MATCH_START}, StopSel={MATCH_END}, HighlightAll=true') AS headline \
+             FROM tasks, to_tsquery('simple', "
+        ));
+        page_query.push_bind(&ts_query).push(format!(
+            ") AS search_query \
+                 WHERE deleted_at IS NULL AND {SEARCH_DOCUMENT} @@ search_query"
+        ));
+
+        if let Some(cursor) = &query.cursor {
+            page_query
+                .push(format!(" AND ({rank} < "))
+                .push_bind(cursor.rank)
+                .push(format!(" OR {rank} = "))
+                .push_bind(cursor.rank)
+                .push(" AND task_id > ")
+                .push_bind(cursor.task_id)
+                .push(")");
+        }
+
+        page_query
+            .push(" ORDER BY rank DESC, task_id ASC LIMIT ")
+            .push_bind(query.limit + 1)
+            .push(" OFFSET ")
+            .push_bind(query.offset);
+
+        let rows: Vec<SearchRow> = page_query
+            .build_query_as()
+            .fetch_all(&mut *connection)
+            .await?;
+
+        let has_more = rows.len() as i64 > query.limit;
+        let hits = rows
+            .into_iter()
+            .take(query.limit as usize)
+            .map(|row| SearchHit {
+                task: row.task,
+                rank: row.rank,
+                snippet: search::snippet_html(&row.headline),
+            })
+            .collect();
+
+        Ok(SearchPage {
+            hits,
+            total,
+            has_more,
+        })
+    }
+
+    async fn count(&self) -> Result<i64, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE deleted_at IS NULL")
+            .fetch_one(&mut *connection)
+            .await
+    }
+
+    async fn find_by_id(&self, task_id: i32) -> Result<Option<TaskRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        find_live_task(&mut connection, task_id).await
+    }
+
+    async fn subtree(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error> {
+        let mut connection = self.acquire().await?;
+
+        let tasks = sqlx::query_as::<_, TaskRow>(&format!(
+            "WITH RECURSIVE subtree AS ( \
+                 SELECT task_id, ARRAY[task_id] AS path FROM tasks \
+                 WHERE task_id = $1 AND deleted_at IS NULL \
+                 UNION ALL \
+                 SELECT tasks.task_id, subtree.path || tasks.task_id \
+                 FROM tasks JOIN subtree ON tasks.parent_task_id = subtree.task_id \
+                 WHERE tasks.deleted_at IS NULL \
+             ) \
+             SELECT {TASK_COLUMNS} FROM tasks JOIN subtree USING (task_id) \
+             ORDER BY subtree.path"
+        ))
+        .bind(task_id)
+        .fetch_all(&mut *connection)
+        .await?;
+
+        Ok((!tasks.is_empty()).then_some(tasks))
+    }
+
+    async fn create(&self, task: CreateTaskReq) -> Result<CreateTaskRow, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let row = sqlx::query_as::<_, CreateTaskRow>(
+            "INSERT INTO tasks (name, priority, due_at, start_at, parent_task_id) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING task_id",
+        )
+        .bind(task.name)
+        .bind(task.priority)
+        .bind(task.due_at)
+        .bind(task.start_at)
+        .bind(task.parent_task_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        touch_ancestors(&mut tx, row.task_id).await?;
+        tx.commit().await?;
+
+        Ok(row)
+    }
+
+    async fn update(
+        &self,
+        task_id: i32,
+        changes: TaskChanges,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error> {
+        if changes.is_empty() {
+            let mut select_query = QueryBuilder::new(format!(
+                "SELECT {TASK_COLUMNS} FROM tasks WHERE deleted_at IS NULL AND task_id = "
+            ));
+            select_query.push_bind(task_id);
+            push_version_guard(&mut select_query, expected_versions);
+
+            let mut connection = self.acquire().await?;
+
+            return select_query
+                .build_query_as()
+                .fetch_optional(&mut *connection)
+                .await;
+        }
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let mut lock_query = QueryBuilder::new("SELECT parent_task_id FROM tasks WHERE task_id = ");
+        lock_query
+            .push_bind(task_id)
+            .push(" AND deleted_at IS NULL");
+        push_version_guard(&mut lock_query, expected_versions);
+        lock_query.push(" FOR UPDATE");
+
+        let Some(old_parent_task_id) = lock_query
+            .build_query_scalar::<Option<i32>>()
+            .fetch_optional(&mut *tx)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        // Moving the task changes the progress of its old ancestors and of its new ones.
+        let moves = changes
+            .parent_task_id
+            .is_some_and(|parent_task_id| parent_task_id != old_parent_task_id);
+
+        if moves {
+            touch_ancestors(&mut tx, task_id).await?;
+        }
+
+        let mut update_query =
+            QueryBuilder::new("UPDATE tasks SET version = version + 1, updated_at = NOW()");
+
+        if let Some(name) = changes.name {
+            update_query.push(", name = ").push_bind(name);
+        }
+
+        if let Some(priority) = changes.priority {
+            update_query.push(", priority = ").push_bind(priority);
+        }
+
+        if let Some(due_at) = changes.due_at {
+            update_query.push(", due_at = ").push_bind(due_at);
+        }
+
+        if let Some(start_at) = changes.start_at {
+            update_query.push(", start_at = ").push_bind(start_at);
+        }
+
+        if let Some(parent_task_id) = changes.parent_task_id {
+            update_query
+                .push(", parent_task_id = ")
+                .push_bind(parent_task_id);
+        }
+
+        let task = update_query
+            .push(" WHERE task_id = ")
+            .push_bind(task_id)
+            .push(format!(" RETURNING {TASK_COLUMNS}"))
+            .build_query_as()
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if moves {
+            touch_ancestors(&mut tx, task_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn set_status(
+        &self,
+        task_id: i32,
+        status: TaskStatus,
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut update_query = QueryBuilder::new(
+            "UPDATE tasks SET version = version + 1, updated_at = NOW(), status = ",
+        );
+        update_query
+            .push_bind(status)
+            .push(", completed_at = ")
+            .push(match status {
+                TaskStatus::Done => "NOW()",
+                _ => "NULL",
+            })
+            .push(" WHERE task_id = ")
+            .push_bind(task_id)
+            .push(" AND deleted_at IS NULL");
+        push_version_guard(&mut update_query, expected_versions);
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let task = update_query
+            .push(format!(" RETURNING {TASK_COLUMNS}"))
+            .build_query_as()
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if task.is_some() {
+            touch_ancestors(&mut tx, task_id).await?;
+            touch_dependents(&mut tx, &[task_id]).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, expected_versions).await? {
+            return Ok(false);
+        }
+
+        touch_ancestors(&mut tx, task_id).await?;
+
+        let deleted_ids: Vec<i32> = match subtasks {
+            SubtasksOnDelete::Orphan => {
+                detach_subtasks(&mut tx, task_id).await?;
+                vec![task_id]
+            }
+            SubtasksOnDelete::Cascade => {
+                sqlx::query_scalar(&format!("{SUBTREE} SELECT task_id FROM subtree"))
+                    .bind(task_id)
+                    .fetch_all(&mut *tx)
+                    .await?
+            }
+        };
+
+        // Before the dependencies go away with the tasks.
+        touch_dependents(&mut tx, &deleted_ids).await?;
+
+        sqlx::query("DELETE FROM tasks WHERE task_id = ANY($1)")
+            .bind(&deleted_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn soft_delete(
+        &self,
+        task_id: i32,
+        expected_versions: Option<&[i32]>,
+        subtasks: SubtasksOnDelete,
+    ) -> Result<bool, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, expected_versions).await? {
+            return Ok(false);
+        }
+
+        touch_ancestors(&mut tx, task_id).await?;
+
+        let trash_query = match subtasks {
+            SubtasksOnDelete::Orphan => {
+                detach_subtasks(&mut tx, task_id).await?;
+                format!("{TRASH_TASKS} WHERE task_id = $1")
+            }
+            SubtasksOnDelete::Cascade => format!(
+                "{SUBTREE} {TRASH_TASKS} \
+                 WHERE task_id IN (SELECT task_id FROM subtree) AND deleted_at IS NULL"
+            ),
+        };
+
+        let trashed_ids: Vec<i32> = sqlx::query_scalar(&format!("{trash_query} RETURNING task_id"))
+            .bind(task_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        touch_dependents(&mut tx, &trashed_ids).await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn restore(&self, task_id: i32) -> Result<RestoreOutcome, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let Some((parent_task_id, deleted_at)) = sqlx::query_as::<_, (Option<i32>, DateTime<Utc>)>(
+            "SELECT parent_task_id, deleted_at FROM tasks \
+             WHERE task_id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
+        )
+        .bind(task_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(RestoreOutcome::NotInTrash);
+        };
+
+        if let Some(parent_task_id) = parent_task_id {
+            let parent_is_live = sqlx::query(
+                "SELECT 1 FROM tasks WHERE task_id = $1 AND deleted_at IS NULL FOR SHARE",
+            )
+            .bind(parent_task_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+
+            if !parent_is_live {
+                return Ok(RestoreOutcome::ParentInTrash(parent_task_id));
+            }
+        }
+
+        // A cascading trash stamps the whole subtree at once, so the subtasks trashed along
+        // with the task are the ones below it with the same `deleted_at`.
+        let restored_ids: Vec<i32> = sqlx::query_scalar(
+            "WITH RECURSIVE trashed_together AS ( \
+                 SELECT task_id FROM tasks WHERE task_id = $1 \
+                 UNION \
+                 SELECT tasks.task_id FROM tasks \
+                 JOIN trashed_together ON tasks.parent_task_id = trashed_together.task_id \
+                 WHERE tasks.deleted_at = $2 \
+             ) \
+             UPDATE tasks SET deleted_at = NULL, version = version + 1, updated_at = NOW() \
+             WHERE task_id IN (SELECT task_id FROM trashed_together) RETURNING task_id",
+        )
+        .bind(task_id)
+        .bind(deleted_at)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        touch_ancestors(&mut tx, task_id).await?;
+        touch_dependents(&mut tx, &restored_ids).await?;
+        tx.commit().await?;
+
+        Ok(RestoreOutcome::Restored)
+    }
+
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64, Error> {
+        let mut connection = self.acquire().await?;
+
+        let result = sqlx::query("DELETE FROM tasks WHERE deleted_at < $1")
+            .bind(deleted_before)
+            .execute(&mut *connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn list_tags(&self) -> Result<Vec<TagRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, TagRow>("SELECT * FROM tags ORDER BY LOWER(name)")
+            .fetch_all(&mut *connection)
+            .await
+    }
+
+    async fn find_tag(&self, tag_id: i32) -> Result<Option<TagRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, TagRow>("SELECT * FROM tags WHERE tag_id = $1")
+            .bind(tag_id)
+            .fetch_optional(&mut *connection)
+            .await
+    }
+
+    async fn create_tag(&self, tag: CreateTagReq) -> Result<TagRow, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, TagRow>("INSERT INTO tags (name, color) VALUES ($1, $2) RETURNING *")
+            .bind(tag.name)
+            .bind(
+                tag.color
+                    .as_deref()
+                    .unwrap_or(DEFAULT_TAG_COLOR)
+                    .to_lowercase(),
+            )
+            .fetch_one(&mut *connection)
+            .await
+    }
+
+    async fn update_tag(
+        &self,
+        tag_id: i32,
+        changes: UpdateTagReq,
+    ) -> Result<Option<TagRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let tag = sqlx::query_as::<_, TagRow>(
+            "UPDATE tags SET name = COALESCE($2, name), color = COALESCE($3, color) \
+             WHERE tag_id = $1 RETURNING *",
+        )
+        .bind(tag_id)
+        .bind(changes.name)
+        .bind(changes.color.map(|color| color.to_lowercase()))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if tag.is_some() {
+            touch_tagged_tasks(&mut tx, tag_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(tag)
+    }
+
+    async fn delete_tag(&self, tag_id: i32) -> Result<bool, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        touch_tagged_tasks(&mut tx, tag_id).await?;
+
+        let result = sqlx::query("DELETE FROM tags WHERE tag_id = $1")
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn merge_tags(&self, tag_id: i32, into_tag_id: i32) -> Result<Option<TagRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let locked: Vec<i32> = sqlx::query_scalar(
+            "SELECT tag_id FROM tags WHERE tag_id = ANY($1) ORDER BY tag_id FOR UPDATE",
+        )
+        .bind(vec![tag_id, into_tag_id])
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if locked.len() != 2 {
+            return Ok(None);
+        }
+
+        touch_tagged_tasks(&mut tx, tag_id).await?;
+
+        sqlx::query(
+            "INSERT INTO task_tags (task_id, tag_id) \
+             SELECT task_id, $2 FROM task_tags WHERE tag_id = $1 \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(tag_id)
+        .bind(into_tag_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM tags WHERE tag_id = $1")
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let tag = sqlx::query_as::<_, TagRow>("SELECT * FROM tags WHERE tag_id = $1")
+            .bind(into_tag_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(tag))
+    }
+
+    async fn attach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, None).await? {
+            return Ok(None);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO task_tags (task_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(task_id)
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        let task = find_live_task(&mut tx, task_id).await?;
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn detach_tag(&self, task_id: i32, tag_id: i32) -> Result<Option<TaskRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, None).await? {
+            return Ok(None);
+        }
+
+        let result = sqlx::query("DELETE FROM task_tags WHERE task_id = $1 AND tag_id = $2")
+            .bind(task_id)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        let task = find_live_task(&mut tx, task_id).await?;
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn blockers(&self, task_id: i32) -> Result<Option<Vec<TaskRow>>, Error> {
+        let mut connection = self.acquire().await?;
+
+        if find_live_task(&mut connection, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let blockers = sqlx::query_as::<_, TaskRow>(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks \
+             JOIN task_dependencies ON task_dependencies.blocker_task_id = tasks.task_id \
+             WHERE task_dependencies.task_id = $1 AND tasks.deleted_at IS NULL \
+             ORDER BY tasks.task_id"
+        ))
+        .bind(task_id)
+        .fetch_all(&mut *connection)
+        .await?;
+
+        Ok(Some(blockers))
+    }
+
+    async fn add_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, None).await? {
+            return Ok(None);
+        }
+
+        let blocker = sqlx::query(
+            "SELECT 1 FROM tasks WHERE task_id = $1 AND deleted_at IS NULL FOR KEY SHARE",
+        )
+        .bind(blocker_task_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if blocker.is_none() {
+            return Ok(None);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO task_dependencies (task_id, blocker_task_id) VALUES ($1, $2) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(task_id)
+        .bind(blocker_task_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        let task = find_live_task(&mut tx, task_id).await?;
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn remove_dependency(
+        &self,
+        task_id: i32,
+        blocker_task_id: i32,
+    ) -> Result<Option<TaskRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        if !lock_guarded_task(&mut tx, task_id, None).await? {
+            return Ok(None);
+        }
+
+        let result = sqlx::query(
+            "DELETE FROM task_dependencies WHERE task_id = $1 AND blocker_task_id = $2",
+        )
+        .bind(task_id)
+        .bind(blocker_task_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        let task = find_live_task(&mut tx, task_id).await?;
+        tx.commit().await?;
+
+        Ok(task)
+    }
+
+    async fn open_task_graph(&self) -> Result<TaskGraph, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        // Both reads have to see the same tasks.
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+
+        let tasks = sqlx::query_as::<_, TaskRow>(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks \
+             WHERE deleted_at IS NULL AND status NOT IN ('done', 'cancelled') \
+             ORDER BY task_id"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let dependencies: Vec<(i32, i32)> =
+            sqlx::query_as("SELECT task_id, blocker_task_id FROM task_dependencies")
+                .fetch_all(&mut *tx)
+                .await?;
+
+        tx.commit().await?;
+
+        Ok(TaskGraph {
+            tasks,
+            dependencies,
+        })
+    }
+
+    async fn list_comments(
+        &self,
+        task_id: i32,
+        query: &CommentListQuery,
+    ) -> Result<CommentPage, Error> {
+        let mut connection = self.acquire().await?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE task_id = $1")
+            .bind(task_id)
+            .fetch_one(&mut *connection)
+            .await?;
+
+        let mut page_query = QueryBuilder::new("SELECT * FROM comments WHERE task_id = ");
+        page_query.push_bind(task_id);
+
+        if let Some(cursor) = &query.cursor {
+            page_query
+                .push(" AND comment_id > ")
+                .push_bind(cursor.comment_id);
+        }
+
+        page_query
+            .push(" ORDER BY comment_id LIMIT ")
+            .push_bind(query.limit + 1)
+            .push(" OFFSET ")
+            .push_bind(query.offset);
+
+        let mut comments: Vec<CommentRow> = page_query
+            .build_query_as()
+            .fetch_all(&mut *connection)
+            .await?;
+
+        let has_more = comments.len() as i64 > query.limit;
+        comments.truncate(query.limit as usize);
+
+        Ok(CommentPage {
+            comments,
+            total,
+            has_more,
+        })
+    }
+
+    async fn find_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+    ) -> Result<Option<CommentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, CommentRow>(&format!(
+            "SELECT * FROM comments WHERE comment_id = $1 AND task_id = $2 AND {OF_LIVE_TASK}"
+        ))
+        .bind(comment_id)
+        .bind(task_id)
+        .fetch_optional(&mut *connection)
+        .await
+    }
+
+    async fn create_comment(
+        &self,
+        task_id: i32,
+        comment: CreateCommentReq,
+    ) -> Result<Option<CommentRow>, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let comment = sqlx::query_as::<_, CommentRow>(
+            "INSERT INTO comments (task_id, author, body) \
+             SELECT task_id, $2, $3 FROM tasks WHERE task_id = $1 AND deleted_at IS NULL \
+             RETURNING *",
+        )
+        .bind(task_id)
+        .bind(comment.author)
+        .bind(comment.body)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // The task embeds its number of comments.
+        if comment.is_some() {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(comment)
+    }
+
+    async fn update_comment(
+        &self,
+        task_id: i32,
+        comment_id: i32,
+        body: String,
+    ) -> Result<Option<CommentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, CommentRow>(&format!(
+            "UPDATE comments SET body = $3, updated_at = NOW() \
+             WHERE comment_id = $1 AND task_id = $2 AND {OF_LIVE_TASK} RETURNING *"
+        ))
+        .bind(comment_id)
+        .bind(task_id)
+        .bind(body)
+        .fetch_optional(&mut *connection)
+        .await
+    }
+
+    async fn delete_comment(&self, task_id: i32, comment_id: i32) -> Result<bool, Error> {
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await?;
+
+        let result = sqlx::query(&format!(
+            "DELETE FROM comments WHERE comment_id = $1 AND task_id = $2 AND {OF_LIVE_TASK}"
+        ))
+        .bind(comment_id)
+        .bind(task_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let deleted = result.rows_affected() > 0;
+
+        if deleted {
+            touch_task(&mut tx, task_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(deleted)
+    }
+
+    async fn list_attachments(&self, task_id: i32) -> Result<Vec<AttachmentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, AttachmentRow>(
+            "SELECT * FROM attachments WHERE task_id = $1 ORDER BY attachment_id",
+        )
+        .bind(task_id)
+        .fetch_all(&mut *connection)
+        .await
+    }
+
+    async fn find_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, AttachmentRow>(&format!(
+            "SELECT * FROM attachments \
+             WHERE attachment_id = $1 AND task_id = $2 AND {ATTACHED_TO_LIVE_TASK}"
+        ))
+        .bind(attachment_id)
+        .bind(task_id)
+        .fetch_optional(&mut *connection)
+        .await
+    }
+
+    async fn create_attachment(
+        &self,
+        attachment: NewAttachment,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, AttachmentRow>(
+            "INSERT INTO attachments (task_id, file_name, content_type, size, storage_key) \
+             SELECT task_id, $2, $3, $4, $5 FROM tasks WHERE task_id = $1 AND deleted_at IS NULL \
+             RETURNING *",
+        )
+        .bind(attachment.task_id)
+        .bind(attachment.file_name)
+        .bind(attachment.content_type)
+        .bind(attachment.size)
+        .bind(attachment.storage_key)
+        .fetch_optional(&mut *connection)
+        .await
+    }
+
+    async fn delete_attachment(
+        &self,
+        task_id: i32,
+        attachment_id: i32,
+    ) -> Result<Option<AttachmentRow>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_as::<_, AttachmentRow>(&format!(
+            "DELETE FROM attachments \
+             WHERE attachment_id = $1 AND task_id = $2 AND {ATTACHED_TO_LIVE_TASK} RETURNING *"
+        ))
+        .bind(attachment_id)
+        .bind(task_id)
+        .fetch_optional(&mut *connection)
+        .await
+    }
+
+    async fn attachment_storage_keys(&self) -> Result<Vec<String>, Error> {
+        let mut connection = self.acquire().await?;
+
+        sqlx::query_scalar("SELECT storage_key FROM attachments")
+            .fetch_all(&mut *connection)
+            .await
+    }
+}
+
+async fn find_live_task(
+    connection: &mut PgConnection,
+    task_id: i32,
+) -> Result<Option<TaskRow>, Error> {
+    sqlx::query_as::<_, TaskRow>(&format!(
+        "SELECT {TASK_COLUMNS} FROM tasks WHERE task_id = $1 AND deleted_at IS NULL"
+    ))
+    .bind(task_id)
+    .fetch_optional(connection)
+    .await
+}
+
+/// Locks a live task whose version is one of `expected_versions`, when given, for the rest
+/// of the transaction, returning whether there is one.
+async fn lock_guarded_task(
+    connection: &mut PgConnection,
+    task_id: i32,
+    expected_versions: Option<&[i32]>,
+) -> Result<bool, Error> {
+    let mut lock_query = QueryBuilder::new("SELECT 1 FROM tasks WHERE task_id = ");
+    lock_query
+        .push_bind(task_id)
+        .push(" AND deleted_at IS NULL");
+    push_version_guard(&mut lock_query, expected_versions);
+    lock_query.push(" FOR UPDATE");
+
+    let locked = lock_query.build().fetch_optional(connection).await?;
+
+    Ok(locked.is_some())
+}
+
+/// Turns the direct subtasks of a task, trashed or not, into top-level tasks.
+async fn detach_subtasks(connection: &mut PgConnection, task_id: i32) -> Result<(), Error> {
+    sqlx::query(
+        "UPDATE tasks SET parent_task_id = NULL, version = version + 1, updated_at = NOW() \
+         WHERE parent_task_id = $1",
+    )
+    .bind(task_id)
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a write to a task that does not go through `UPDATE tasks`, such as a tag change.
+async fn touch_task(connection: &mut PgConnection, task_id: i32) -> Result<(), Error> {
+    sqlx::query("UPDATE tasks SET version = version + 1, updated_at = NOW() WHERE task_id = $1")
+        .bind(task_id)
+        .execute(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Records a change of the `progress` of every task above `task_id`.
+async fn touch_ancestors(connection: &mut PgConnection, task_id: i32) -> Result<(), Error> {
+    sqlx::query(
+        "WITH RECURSIVE ancestors AS ( \
+             SELECT parent_task_id AS task_id FROM tasks \
+             WHERE task_id = $1 AND parent_task_id IS NOT NULL \
+             UNION \
+             SELECT tasks.parent_task_id FROM tasks JOIN ancestors USING (task_id) \
+             WHERE tasks.parent_task_id IS NOT NULL \
+         ) \
+         UPDATE tasks SET version = version + 1, updated_at = NOW() \
+         WHERE task_id IN (SELECT task_id FROM ancestors)",
+    )
+    .bind(task_id)
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a change of `blockers_done` of every task blocked by one of `blocker_task_ids`.
+async fn touch_dependents(
+    connection: &mut PgConnection,
+    blocker_task_ids: &[i32],
+) -> Result<(), Error> {
+    sqlx::query(
+        "UPDATE tasks SET version = version + 1, updated_at = NOW() \
+         WHERE task_id IN ( \
+             SELECT task_id FROM task_dependencies WHERE blocker_task_id = ANY($1) \
+         )",
+    )
+    .bind(blocker_task_ids)
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a change of the tag embedded in every task it is attached to.
+async fn touch_tagged_tasks(connection: &mut PgConnection, tag_id: i32) -> Result<(), Error> {
+    sqlx::query(
+        "UPDATE tasks SET version = version + 1, updated_at = NOW() \
+         WHERE task_id IN (SELECT task_id FROM task_tags WHERE tag_id = $1)",
+    )
+    .bind(tag_id)
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Joins the tags of the current task, ending with their lowercase name to compare.
+const TAG_NAMES_OF_TASK: &str = "FROM task_tags JOIN tags USING (tag_id) \
+     WHERE task_tags.task_id = tasks.task_id AND LOWER(tags.name)";
+
+/// Mirrors [`TaskRow::is_overdue`]; `NULL` when the task has no due time.
+const OVERDUE: &str = "(due_at < NOW() AND status NOT IN ('done', 'cancelled'))";
+
+/// Only whitelisted column expressions ever reach the SQL string; every value is bound.
+fn sort_expression(sort: &TaskSort) -> &'static str {
+    match sort.field {
+        SortField::TaskId => "task_id",
+        SortField::Name => "name",
+        SortField::Priority => "COALESCE(priority, 2147483647)",
+    }
+}
+
+fn push_version_guard(builder: &mut QueryBuilder<'_, Postgres>, expected_versions: Option<&[i32]>) {
+    if let Some(versions) = expected_versions {
+        builder
+            .push(" AND version = ANY(")
+            .push_bind(versions.to_vec())
+            .push(")");
+    }
+}
+
+fn push_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &TaskFilter) {
+    if filter.deleted {
+        builder.push(" AND deleted_at IS NOT NULL");
+    } else {
+        builder.push(" AND deleted_at IS NULL");
+    }
+
+    if let Some(status) = filter.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+
+    if let Some(parent_task_id) = filter.parent_task_id {
+        builder
+            .push(" AND parent_task_id = ")
+            .push_bind(parent_task_id);
+    }
+
+    if let Some(priority) = filter.priority {
+        builder.push(" AND priority = ").push_bind(priority);
+    }
+
+    if let Some(min) = filter.priority_min {
+        builder.push(" AND priority >= ").push_bind(min);
+    }
+
+    if let Some(max) = filter.priority_max {
+        builder.push(" AND priority <= ").push_bind(max);
+    }
+
+    if let Some(due_after) = filter.due_after {
+        builder.push(" AND due_at >= ").push_bind(due_after);
+    }
+
+    if let Some(due_before) = filter.due_before {
+        builder.push(" AND due_at < ").push_bind(due_before);
+    }
+
+    match filter.overdue {
+        Some(true) => builder.push(format!(" AND {OVERDUE}")),
+        Some(false) => builder.push(format!(" AND NOT COALESCE({OVERDUE}, FALSE)")),
+        None => builder,
+    };
+
+    if !filter.tags_any.is_empty() {
+        builder
+            .push(format!(" AND EXISTS (SELECT 1 {TAG_NAMES_OF_TASK} = ANY("))
+            .push_bind(filter.tags_any.clone())
+            .push("))");
+    }
+
+    if !filter.tags_all.is_empty() {
+        builder
+            .push(format!(" AND (SELECT COUNT(*) {TAG_NAMES_OF_TASK} = ANY("))
+            .push_bind(filter.tags_all.clone())
+            .push(")) = ")
+            .push_bind(filter.tags_all.len() as i64);
+    }
+
+    if let Some(name) = &filter.name_contains {
+        let pattern = name
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        builder
+            .push(" AND name ILIKE ")
+            .push_bind(format!("%{pattern}%"));
+    }
+}
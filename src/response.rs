@@ -0,0 +1,81 @@
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+
+/// Envelope of every successful response: `{"success": true, "data": ..., "meta": ...}`.
+#[derive(Serialize, ToSchema)]
+pub struct ApiResponse<T: Serialize> {
+    #[serde(skip)]
+    status_code: StatusCode,
+    #[serde(skip)]
+    headers: HeaderMap,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<PageMeta>,
+}
+
+/// Pagination details of a listing response.
+#[derive(Serialize, ToSchema)]
+pub struct PageMeta {
+    /// Items matching the filters, across every page.
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    /// Pass as `cursor` to get the next page; `null` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+pub type ApiResult<T> = Result<ApiResponse<T>, AppError>;
+
+impl<T: Serialize> ApiResponse<T> {
+    pub fn ok(data: T) -> Self {
+        Self {
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
+            success: true,
+            data: Some(data),
+            meta: None,
+        }
+    }
+
+    pub fn with_meta(mut self, meta: PageMeta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    pub fn with_etag(mut self, etag: &str) -> Self {
+        if let Ok(value) = HeaderValue::from_str(etag) {
+            self.headers.insert(header::ETAG, value);
+        }
+        self
+    }
+}
+
+impl ApiResponse<()> {
+    /// A successful response with nothing to return besides `"success": true`.
+    pub fn empty() -> Self {
+        Self {
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
+            success: true,
+            data: None,
+            meta: None,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(mut self) -> Response {
+        let headers = std::mem::take(&mut self.headers);
+
+        (self.status_code, headers, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,92 @@
+//! Matching, ranking and highlighting of task searches, as done by Postgres full-text
+//! search. The in-memory repository reproduces it; both highlight with these markers.
+
+/// Opens a match in a headline. Task names are validated to be free of control
+/// characters, so unlike markup these cannot be mistaken for part of a name once the
+/// headline is escaped.
+pub const MATCH_START: char = '\u{2}';
+/// Closes a match in a headline.
+pub const MATCH_END: char = '\u{3}';
+
+/// Lowercase words of `text`, i.e. its runs of letters and digits.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Relevance of `name` to `terms`, every one of which has to start one of its words, or
+/// `None` when it does not match. Stands in for Postgres' `ts_rank`: the more words match,
+/// the higher.
+pub fn rank(name: &str, terms: &[String]) -> Option<f32> {
+    let words: Vec<String> = words(name).collect();
+
+    let every_term_matches = terms
+        .iter()
+        .all(|term| words.iter().any(|word| word.starts_with(term.as_str())));
+    if !every_term_matches {
+        return None;
+    }
+
+    let matching_words = words
+        .iter()
+        .filter(|word| terms.iter().any(|term| word.starts_with(term.as_str())))
+        .count();
+
+    Some(matching_words as f32 / 10.0)
+}
+
+/// `name` with the words starting with one of `terms` between [`MATCH_START`] and
+/// [`MATCH_END`], like Postgres' `ts_headline` with those delimiters.
+pub fn headline(name: &str, terms: &[String]) -> String {
+    let mut headline = String::with_capacity(name.len());
+    let mut word = String::new();
+
+    let flush = |word: &mut String, headline: &mut String| {
+        let lowercase = word.to_lowercase();
+
+        if terms
+            .iter()
+            .any(|term| lowercase.starts_with(term.as_str()))
+        {
+            headline.push(MATCH_START);
+            headline.push_str(word);
+            headline.push(MATCH_END);
+        } else {
+            headline.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut headline);
+            headline.push(c);
+        }
+    }
+    flush(&mut word, &mut headline);
+
+    headline
+}
+
+/// A headline as HTML: its text escaped and its matches wrapped in `<mark>`.
+pub fn snippet_html(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+
+    for c in headline.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
@@ -0,0 +1,82 @@
+use std::{io, time::Duration};
+
+use axum::Router;
+use tokio::{net::TcpListener, sync::watch, time::sleep};
+
+use crate::state::AppState;
+
+/// How the server winds down once asked to stop.
+pub struct DrainOptions {
+    /// Time spent still serving, with readiness failing, so load balancers can react.
+    pub delay: Duration,
+    /// Time in-flight requests get to finish before they are dropped.
+    pub timeout: Duration,
+}
+
+/// Resolves on Ctrl+C (SIGINT) or, on Unix, SIGTERM.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Could not listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Could not listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
+
+/// Serves `app` until a shutdown signal arrives, then flips readiness, stops accepting
+/// connections and waits for in-flight requests for at most `options.timeout`.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    state: AppState,
+    options: DrainOptions,
+) -> io::Result<()> {
+    let (drain_tx, drain_rx) = watch::channel(false);
+
+    tokio::spawn(async move {
+        signal().await;
+        tracing::info!("Shutdown requested, draining in-flight requests");
+
+        state.start_draining();
+        sleep(options.delay).await;
+
+        let _ = drain_tx.send(true);
+    });
+
+    let server = axum::serve(listener, app).with_graceful_shutdown(drain_started(drain_rx.clone()));
+
+    let deadline = async {
+        drain_started(drain_rx).await;
+        sleep(options.timeout).await;
+    };
+
+    tokio::select! {
+        result = server.into_future() => result,
+        () = deadline => {
+            tracing::warn!(
+                drain_timeout_secs = options.timeout.as_secs(),
+                "Requests still running after the drain timeout were dropped"
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn drain_started(mut drain_rx: watch::Receiver<bool>) {
+    let _ = drain_rx.wait_for(|draining| *draining).await;
+}
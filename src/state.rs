@@ -0,0 +1,119 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    attachments::DEFAULT_MAX_ATTACHMENT_BYTES,
+    blob_store::{InMemoryBlobStore, SharedBlobStore},
+    metrics::Metrics,
+    repository::{InMemoryTaskRepository, SharedTaskRepository},
+    workflow::Transitions,
+};
+
+/// What `DELETE /tasks/:task_id` does with the task.
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    /// Removes the row for good.
+    Hard,
+    /// Stamps `deleted_at`, moving the task to the trash until it is restored or purged.
+    Soft,
+}
+
+/// What deleting a task does with its subtasks.
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtasksOnDelete {
+    /// Turns the direct subtasks into top-level tasks.
+    Orphan,
+    /// Deletes every subtask along with the task, the same way as the task.
+    Cascade,
+}
+
+/// Everything the tasks handlers share, handed to [`crate::build_router`].
+#[derive(Clone)]
+pub struct AppState {
+    pub repository: SharedTaskRepository,
+    pub delete_mode: DeleteMode,
+    pub subtasks_on_delete: SubtasksOnDelete,
+    pub transitions: Arc<Transitions>,
+    /// Where the files of attachments are kept.
+    pub blob_store: SharedBlobStore,
+    pub max_attachment_bytes: u64,
+    /// Pool behind the repository, when there is one, checked by the readiness probe.
+    pub db_pool: Option<Pool<Postgres>>,
+    pub metrics: Metrics,
+    draining: Arc<AtomicBool>,
+}
+
+impl AppState {
+    pub fn new(repository: SharedTaskRepository) -> Self {
+        Self {
+            repository,
+            delete_mode: DeleteMode::Hard,
+            subtasks_on_delete: SubtasksOnDelete::Orphan,
+            transitions: Arc::new(Transitions::default()),
+            blob_store: Arc::new(InMemoryBlobStore::new()),
+            max_attachment_bytes: DEFAULT_MAX_ATTACHMENT_BYTES,
+            db_pool: None,
+            metrics: Metrics::new(),
+            draining: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// State backed by an empty [`InMemoryTaskRepository`] and [`InMemoryBlobStore`], for
+    /// tests and demos.
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(InMemoryTaskRepository::new()))
+    }
+
+    pub fn with_delete_mode(mut self, delete_mode: DeleteMode) -> Self {
+        self.delete_mode = delete_mode;
+        self
+    }
+
+    pub fn with_subtasks_on_delete(mut self, subtasks_on_delete: SubtasksOnDelete) -> Self {
+        self.subtasks_on_delete = subtasks_on_delete;
+        self
+    }
+
+    pub fn with_transitions(mut self, transitions: Transitions) -> Self {
+        self.transitions = Arc::new(transitions);
+        self
+    }
+
+    pub fn with_blob_store(mut self, blob_store: SharedBlobStore) -> Self {
+        self.blob_store = blob_store;
+        self
+    }
+
+    pub fn with_max_attachment_bytes(mut self, max_attachment_bytes: u64) -> Self {
+        self.max_attachment_bytes = max_attachment_bytes;
+        self
+    }
+
+    pub fn with_db_pool(mut self, db_pool: Pool<Postgres>) -> Self {
+        self.db_pool = Some(db_pool);
+        self
+    }
+
+    /// Shares `metrics` with whatever else reports to it, such as a [`crate::repository::PgTaskRepository`].
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Marks the service as shutting down, so readiness probes stop routing traffic to it.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    extract::{MatchedPath, Request},
+    http::HeaderName,
+    response::Response,
+};
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{Span, field};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+/// Header carrying the request ID; kept from the request when present, generated otherwise.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Installs the global subscriber. `level` is an `EnvFilter` directive such as `info`
+/// or `info,sqlx::query=debug`.
+pub fn init(logging: &LoggingConfig) {
+    let filter = EnvFilter::try_new(&logging.level)
+        .unwrap_or_else(|err| panic!("Invalid log level {:?}: {err}", logging.level));
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match logging.format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber
+            .json()
+            .flatten_event(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
+
+/// Wraps `router` so every request gets an ID and runs in a span that records
+/// its method, route template, task ID, status and latency.
+pub fn trace_requests(router: Router) -> Router {
+    router
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_request(())
+                .on_response(record_response),
+        )
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+}
+
+fn request_span(request: &Request) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str);
+    let task_id = route.and_then(|route| path_param(route, request.uri().path(), "task_id"));
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route,
+        task_id,
+        request_id,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    )
+}
+
+fn record_response(response: &Response, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", latency.as_secs_f64() * 1000.0);
+
+    tracing::info!("finished processing request");
+}
+
+/// Value of the `:name` segment of `route` in the concrete `path`.
+fn path_param<'a>(route: &str, path: &'a str, name: &str) -> Option<&'a str> {
+    route
+        .split('/')
+        .zip(path.split('/'))
+        .find_map(|(template, segment)| {
+            (template.strip_prefix(':') == Some(name)).then_some(segment)
+        })
+}
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::TaskStatus;
+
+/// Allowed status changes, as the statuses each status may move to. A status missing
+/// from the map cannot be left at all.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Transitions(BTreeMap<TaskStatus, Vec<TaskStatus>>);
+
+impl Transitions {
+    pub fn new(graph: BTreeMap<TaskStatus, Vec<TaskStatus>>) -> Self {
+        Self(graph)
+    }
+
+    pub fn allows(&self, from: TaskStatus, to: TaskStatus) -> bool {
+        self.0
+            .get(&from)
+            .is_some_and(|targets| targets.contains(&to))
+    }
+}
+
+impl Default for Transitions {
+    /// Open tasks move freely between each other and can be cancelled, or completed
+    /// unless blocked; finished tasks can only be reopened.
+    fn default() -> Self {
+        use TaskStatus::*;
+
+        Self::new(BTreeMap::from([
+            (Todo, vec![InProgress, Blocked, Done, Cancelled]),
+            (InProgress, vec![Todo, Blocked, Done, Cancelled]),
+            (Blocked, vec![Todo, InProgress, Cancelled]),
+            (Done, vec![Todo]),
+            (Cancelled, vec![Todo]),
+        ]))
+    }
+}
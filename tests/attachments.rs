@@ -0,0 +1,88 @@
+//! The sweep of orphaned attachment files, which must never touch blobs it did not write,
+//! whatever the attachment store.
+
+use std::{env, fs, sync::Arc};
+
+use bytes::Bytes;
+use chrono::{Duration, Utc};
+use first_axum_postgres_crud::{
+    attachments::{storage_key, sweep_orphaned_blobs},
+    blob_store::{InMemoryBlobStore, LocalBlobStore, SharedBlobStore},
+    models::{CreateTaskReq, NewAttachment},
+    repository::{InMemoryTaskRepository, SharedTaskRepository},
+};
+use futures_util::{StreamExt, stream};
+use uuid::Uuid;
+
+async fn put(blob_store: &SharedBlobStore, key: &str) {
+    let body = stream::once(async { Ok(Bytes::from_static(b"contents")) }).boxed();
+
+    blob_store.put(key, body).await.unwrap();
+}
+
+async fn exists(blob_store: &SharedBlobStore, key: &str) -> bool {
+    blob_store.get(key).await.unwrap().is_some()
+}
+
+/// Fills `blob_store` with an attachment file, an orphaned one and blobs of someone else
+/// sharing the store, then checks that the sweep only deletes the orphan.
+async fn sweep_deletes_only_orphaned_attachment_files(blob_store: SharedBlobStore) {
+    let repository: SharedTaskRepository = Arc::new(InMemoryTaskRepository::new());
+
+    let task = repository
+        .create(CreateTaskReq {
+            name: "Write the report".to_owned(),
+            priority: None,
+            due_at: None,
+            start_at: None,
+            parent_task_id: None,
+        })
+        .await
+        .unwrap();
+
+    let attached_key = storage_key(task.task_id);
+    put(&blob_store, &attached_key).await;
+    repository
+        .create_attachment(NewAttachment {
+            task_id: task.task_id,
+            file_name: "report.txt".to_owned(),
+            content_type: "text/plain".to_owned(),
+            size: 8,
+            storage_key: attached_key.clone(),
+        })
+        .await
+        .unwrap();
+
+    let orphaned_key = storage_key(task.task_id);
+    put(&blob_store, &orphaned_key).await;
+
+    let foreign_keys = ["backups/tasks.dump", "tasks.csv", "taskstore/1/notes.txt"];
+    for key in foreign_keys {
+        put(&blob_store, key).await;
+    }
+
+    let deleted = sweep_orphaned_blobs(&repository, &blob_store, Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+
+    assert_eq!(deleted, 1);
+    assert!(!exists(&blob_store, &orphaned_key).await);
+    assert!(exists(&blob_store, &attached_key).await);
+    for key in foreign_keys {
+        assert!(exists(&blob_store, key).await, "{key} was deleted");
+    }
+}
+
+#[tokio::test]
+async fn in_memory_sweep_keeps_foreign_blobs() {
+    sweep_deletes_only_orphaned_attachment_files(Arc::new(InMemoryBlobStore::new())).await;
+}
+
+#[tokio::test]
+async fn local_sweep_keeps_foreign_files() {
+    let root = env::temp_dir().join(format!("attachments-sweep-{}", Uuid::new_v4()));
+
+    sweep_deletes_only_orphaned_attachment_files(Arc::new(LocalBlobStore::new(&root))).await;
+
+    fs::remove_dir_all(root).unwrap();
+}
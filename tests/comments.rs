@@ -0,0 +1,36 @@
+//! Comment threads through the router.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, named};
+use first_axum_postgres_crud::AppState;
+use serde_json::json;
+
+#[tokio::test]
+async fn comment_html_is_sanitized() {
+    let app = TestApp::new(AppState::in_memory());
+    let task_id = app.create_task(named("Write the report")).await;
+
+    let response = app
+        .post(
+            &format!("/tasks/{task_id}/comments"),
+            json!({
+                "author": "mallory",
+                "body": "**Done**, see [the draft](javascript:alert(1)) <script>alert(2)</script>\
+                    <a href=\"javascript:alert(3)\" onclick=\"alert(4)\">here</a>",
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let html = response.data()["body_html"].as_str().unwrap();
+    assert!(html.contains("<strong>Done</strong>"), "{html}");
+    assert!(html.contains("the draft"), "{html}");
+    for unsafe_fragment in ["<script", "alert(2)", "javascript:", "onclick"] {
+        assert!(
+            !html.contains(unsafe_fragment),
+            "{unsafe_fragment} kept in {html}"
+        );
+    }
+}
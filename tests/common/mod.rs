@@ -0,0 +1,120 @@
+//! A client for driving the whole application in-process, over the in-memory backend.
+
+#![allow(dead_code)]
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+};
+use first_axum_postgres_crud::{AppState, build_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+pub struct TestApp {
+    router: Router,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The parsed JSON body, or `null` when there is none.
+    pub body: Value,
+}
+
+impl TestResponse {
+    /// `data` of a successful response.
+    pub fn data(&self) -> &Value {
+        &self.body["data"]
+    }
+
+    /// `code` of a problem response.
+    pub fn code(&self) -> &str {
+        self.body["code"].as_str().unwrap_or_default()
+    }
+
+    pub fn etag(&self) -> HeaderValue {
+        self.headers[header::ETAG].clone()
+    }
+}
+
+impl TestApp {
+    /// The standalone application over `state`.
+    pub fn new(state: AppState) -> Self {
+        Self::with_router(build_app(state))
+    }
+
+    pub fn with_router(router: Router) -> Self {
+        Self { router }
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap()
+        };
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.send(request(Method::GET, uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    pub async fn post(&self, uri: &str, body: Value) -> TestResponse {
+        self.send(with_json(request(Method::POST, uri), body)).await
+    }
+
+    pub async fn patch(&self, uri: &str, body: Value) -> TestResponse {
+        self.send(with_json(request(Method::PATCH, uri), body))
+            .await
+    }
+
+    pub async fn put(&self, uri: &str) -> TestResponse {
+        self.send(request(Method::PUT, uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    pub async fn delete(&self, uri: &str) -> TestResponse {
+        self.send(request(Method::DELETE, uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    /// Creates a task from `body` and returns its ID.
+    pub async fn create_task(&self, body: Value) -> i64 {
+        let response = self.post("/tasks", body).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        response.data()["task_id"].as_i64().unwrap()
+    }
+
+    pub async fn task(&self, task_id: i64) -> TestResponse {
+        self.get(&format!("/tasks/{task_id}")).await
+    }
+}
+
+pub fn request(method: Method, uri: &str) -> axum::http::request::Builder {
+    Request::builder().method(method).uri(uri)
+}
+
+pub fn with_json(builder: axum::http::request::Builder, body: Value) -> Request<Body> {
+    builder
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+pub fn named(name: &str) -> Value {
+    json!({ "name": name })
+}
@@ -0,0 +1,105 @@
+//! Due-date filters of `GET /tasks`, including days that are not 24 hours long.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use first_axum_postgres_crud::AppState;
+use serde_json::json;
+
+/// An app with one task per due date, named after it.
+async fn app_with_tasks_due(due_dates: &[&str]) -> TestApp {
+    let app = TestApp::new(AppState::in_memory());
+    for due_at in due_dates {
+        app.create_task(json!({ "name": due_at, "due_at": due_at }))
+            .await;
+    }
+
+    app
+}
+
+/// Names of the tasks listed by `uri`, which has to succeed.
+async fn names(app: &TestApp, uri: &str) -> Vec<String> {
+    let response = app.get(uri).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["name"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn due_after_is_inclusive_and_due_before_exclusive() {
+    let app = app_with_tasks_due(&["2024-05-01T08:00:00Z", "2024-05-01T12:00:00Z"]).await;
+
+    assert_eq!(
+        names(
+            &app,
+            "/tasks?due_after=2024-05-01T08:00:00Z&due_before=2024-05-01T12:00:00Z"
+        )
+        .await,
+        ["2024-05-01T08:00:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn due_on_covers_a_23_hour_day_in_tz() {
+    // Paris moves to summer time at 02:00 on 2024-03-31, so that day spans from 23:00 UTC
+    // the day before to 22:00 UTC.
+    let app = app_with_tasks_due(&[
+        "2024-03-30T22:30:00Z",
+        "2024-03-30T23:30:00Z",
+        "2024-03-31T21:30:00Z",
+        "2024-03-31T22:30:00Z",
+    ])
+    .await;
+
+    assert_eq!(
+        names(&app, "/tasks?due_on=2024-03-31&tz=Europe/Paris").await,
+        ["2024-03-30T23:30:00Z", "2024-03-31T21:30:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn due_on_starts_after_a_skipped_midnight() {
+    // Santiago skips from 00:00 to 01:00 on 2024-09-08, so that day starts at 04:00 UTC.
+    let app = app_with_tasks_due(&["2024-09-08T03:30:00Z", "2024-09-08T04:30:00Z"]).await;
+
+    assert_eq!(
+        names(&app, "/tasks?due_on=2024-09-08&tz=America/Santiago").await,
+        ["2024-09-08T04:30:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn unknown_tz_is_rejected() {
+    let app = TestApp::new(AppState::in_memory());
+
+    let response = app.get("/tasks?due_on=today&tz=Mars/Olympus_Mons").await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn overdue_leaves_out_closed_and_future_tasks() {
+    let app = TestApp::new(AppState::in_memory());
+    for name in ["Open", "Done", "Cancelled"] {
+        app.create_task(json!({ "name": name, "due_at": "2000-01-01T00:00:00Z" }))
+            .await;
+    }
+    app.create_task(json!({ "name": "Later", "due_at": "2999-01-01T00:00:00Z" }))
+        .await;
+    app.post("/tasks/2/complete", json!({})).await;
+    app.post("/tasks/3/cancel", json!({})).await;
+
+    assert_eq!(names(&app, "/tasks?overdue=true").await, ["Open"]);
+    assert_eq!(
+        names(&app, "/tasks?overdue=false").await,
+        ["Done", "Cancelled", "Later"]
+    );
+}
@@ -0,0 +1,185 @@
+//! Subtasks and dependencies through the router: cycle checks, the execution order, the
+//! trash, and the derived fields that have to move a task's entity tag.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, named};
+use first_axum_postgres_crud::{AppState, DeleteMode, SubtasksOnDelete};
+use serde_json::json;
+
+fn app() -> TestApp {
+    TestApp::new(AppState::in_memory())
+}
+
+/// An app moving deleted tasks to the trash along with their subtasks.
+fn app_with_trash() -> TestApp {
+    TestApp::new(
+        AppState::in_memory()
+            .with_delete_mode(DeleteMode::Soft)
+            .with_subtasks_on_delete(SubtasksOnDelete::Cascade),
+    )
+}
+
+#[tokio::test]
+async fn moving_a_task_under_its_own_subtask_is_a_conflict() {
+    let app = app();
+    let parent_id = app.create_task(named("Release")).await;
+    let child_id = app
+        .create_task(json!({ "name": "Tag the build", "parent_task_id": parent_id }))
+        .await;
+
+    let response = app
+        .patch(
+            &format!("/tasks/{parent_id}"),
+            json!({ "parent_task_id": child_id }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "subtask_cycle");
+}
+
+#[tokio::test]
+async fn progress_counts_done_subtasks_at_any_depth_but_not_cancelled_ones() {
+    let app = app();
+    let root_id = app.create_task(named("Release")).await;
+    let child_id = app
+        .create_task(json!({ "name": "Build", "parent_task_id": root_id }))
+        .await;
+    let grandchild_id = app
+        .create_task(json!({ "name": "Compile", "parent_task_id": child_id }))
+        .await;
+    let cancelled_id = app
+        .create_task(json!({ "name": "Write a blog post", "parent_task_id": root_id }))
+        .await;
+    assert_eq!(app.task(root_id).await.data()["progress"], 0);
+
+    app.post(&format!("/tasks/{grandchild_id}/complete"), json!({}))
+        .await;
+    app.post(&format!("/tasks/{cancelled_id}/cancel"), json!({}))
+        .await;
+
+    assert_eq!(app.task(root_id).await.data()["progress"], 50);
+    assert_eq!(app.task(child_id).await.data()["progress"], 100);
+    assert_eq!(
+        app.task(grandchild_id).await.data()["progress"],
+        json!(null)
+    );
+}
+
+#[tokio::test]
+async fn a_dependency_closing_a_cycle_is_a_conflict() {
+    let app = app();
+    let first_id = app.create_task(named("Design")).await;
+    let second_id = app.create_task(named("Build")).await;
+    app.put(&format!("/tasks/{second_id}/dependencies/{first_id}"))
+        .await;
+
+    let response = app
+        .put(&format!("/tasks/{first_id}/dependencies/{second_id}"))
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "dependency_cycle");
+}
+
+#[tokio::test]
+async fn execution_order_puts_blockers_first() {
+    let app = app();
+    let ship_id = app.create_task(named("Ship")).await;
+    let build_id = app.create_task(named("Build")).await;
+    let design_id = app.create_task(named("Design")).await;
+    app.put(&format!("/tasks/{ship_id}/dependencies/{build_id}"))
+        .await;
+    app.put(&format!("/tasks/{build_id}/dependencies/{design_id}"))
+        .await;
+
+    let response = app.get("/tasks/order").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let order: Vec<i64> = response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["task_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(order, [design_id, build_id, ship_id]);
+}
+
+#[tokio::test]
+async fn completing_a_subtask_changes_the_parent_etag() {
+    let app = app();
+    let parent_id = app.create_task(named("Release")).await;
+    let child_id = app
+        .create_task(json!({ "name": "Tag the build", "parent_task_id": parent_id }))
+        .await;
+    let before = app.task(parent_id).await;
+
+    app.post(&format!("/tasks/{child_id}/complete"), json!({}))
+        .await;
+    let after = app.task(parent_id).await;
+
+    assert_ne!(after.etag(), before.etag());
+    assert_ne!(after.data()["progress"], before.data()["progress"]);
+}
+
+#[tokio::test]
+async fn restoring_a_task_restores_the_subtasks_trashed_with_it() {
+    let app = app_with_trash();
+    let parent_id = app.create_task(named("Release")).await;
+    let kept_id = app
+        .create_task(json!({ "name": "Tag the build", "parent_task_id": parent_id }))
+        .await;
+    let dropped_id = app
+        .create_task(json!({ "name": "Write a blog post", "parent_task_id": parent_id }))
+        .await;
+    app.delete(&format!("/tasks/{dropped_id}")).await;
+    app.delete(&format!("/tasks/{parent_id}")).await;
+
+    let response = app
+        .post(&format!("/tasks/{parent_id}/restore"), json!({}))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.task(parent_id).await.status, StatusCode::OK);
+    assert_eq!(app.task(kept_id).await.status, StatusCode::OK);
+    assert_eq!(app.task(dropped_id).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn restoring_a_subtask_of_a_trashed_task_is_a_conflict() {
+    let app = app_with_trash();
+    let parent_id = app.create_task(named("Release")).await;
+    let child_id = app
+        .create_task(json!({ "name": "Tag the build", "parent_task_id": parent_id }))
+        .await;
+    app.delete(&format!("/tasks/{parent_id}")).await;
+
+    let response = app
+        .post(&format!("/tasks/{child_id}/restore"), json!({}))
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "parent_in_trash");
+    assert_eq!(app.task(child_id).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn completing_a_blocker_changes_the_dependent_etag() {
+    let app = app();
+    let blocker_id = app.create_task(named("Design")).await;
+    let dependent_id = app.create_task(named("Build")).await;
+    app.put(&format!("/tasks/{dependent_id}/dependencies/{blocker_id}"))
+        .await;
+    let before = app.task(dependent_id).await;
+
+    app.post(&format!("/tasks/{blocker_id}/complete"), json!({}))
+        .await;
+    let after = app.task(dependent_id).await;
+
+    assert_ne!(after.etag(), before.etag());
+    assert_eq!(before.data()["blockers_done"], false);
+    assert_eq!(after.data()["blockers_done"], true);
+}
@@ -0,0 +1,121 @@
+//! Keeps the OpenAPI document in step with the router and with the committed `openapi.json`.
+//!
+//! After an intended API change, refresh the committed document with
+//! `UPDATE_OPENAPI=1 cargo test --test openapi`.
+
+use std::{env, fs, path::PathBuf};
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use first_axum_postgres_crud::{AppState, build_app, openapi};
+use tower::ServiceExt;
+
+const METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+];
+
+fn committed_spec_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json")
+}
+
+/// Every documented path with its documented methods.
+fn documented_operations() -> Vec<(String, Vec<Method>)> {
+    let spec = serde_json::to_value(openapi::spec()).unwrap();
+
+    spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(path, item)| {
+            let methods = METHODS
+                .iter()
+                .filter(|method| item.get(method.as_str().to_lowercase()).is_some())
+                .cloned()
+                .collect();
+
+            (path.clone(), methods)
+        })
+        .collect()
+}
+
+/// A concrete URI for a templated OpenAPI path.
+fn concrete_uri(path: &str) -> String {
+    path.replace("{task_id}", "1")
+        .replace("{tag_id}", "1")
+        .replace("{blocker_task_id}", "2")
+        .replace("{comment_id}", "1")
+        .replace("{attachment_id}", "1")
+}
+
+/// Sends `method uri` to a fresh in-memory app, returning the status and whether the
+/// answer came from a handler rather than the router's fallback.
+async fn send(method: Method, uri: &str) -> (StatusCode, bool) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+
+    let response = build_app(AppState::in_memory())
+        .oneshot(request)
+        .await
+        .unwrap();
+
+    let from_handler = response.headers().contains_key(header::CONTENT_TYPE);
+
+    (response.status(), from_handler)
+}
+
+#[test]
+fn spec_matches_the_committed_document() {
+    let generated = serde_json::to_string_pretty(&openapi::spec()).unwrap() + "\n";
+    let path = committed_spec_path();
+
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        fs::write(&path, &generated).unwrap();
+        return;
+    }
+
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+
+    assert!(
+        committed == generated,
+        "openapi.json is out of date; run `UPDATE_OPENAPI=1 cargo test --test openapi` and review the diff"
+    );
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_routed() {
+    for (path, methods) in documented_operations() {
+        for method in methods {
+            let (status, from_handler) = send(method.clone(), &concrete_uri(&path)).await;
+
+            assert!(
+                status != StatusCode::METHOD_NOT_ALLOWED
+                    && (status != StatusCode::NOT_FOUND || from_handler),
+                "{method} {path} is documented but not routed (got {status})"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn every_routed_method_of_a_documented_path_is_documented() {
+    for (path, methods) in documented_operations() {
+        for method in METHODS.iter().filter(|method| !methods.contains(method)) {
+            let (status, _) = send(method.clone(), &concrete_uri(&path)).await;
+
+            assert_eq!(
+                status,
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{method} {path} is routed but not documented"
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Searching task names through the router.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, named};
+use first_axum_postgres_crud::AppState;
+
+#[tokio::test]
+async fn search_ranks_every_matching_task() {
+    let app = TestApp::new(AppState::in_memory());
+    for name in ["Write the report", "Review the report", "Water the plants"] {
+        app.create_task(named(name)).await;
+    }
+
+    let response = app.get("/tasks/search?q=REP").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["meta"]["total"], 2);
+    let snippets: Vec<&str> = response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["snippet"].as_str().unwrap())
+        .collect();
+    assert!(snippets.contains(&"Write the <mark>report</mark>"));
+    assert!(snippets.contains(&"Review the <mark>report</mark>"));
+}
+
+#[tokio::test]
+async fn search_without_words_is_rejected() {
+    let response = TestApp::new(AppState::in_memory())
+        .get("/tasks/search?q=%20-%20")
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn search_cursor_only_continues_its_own_query() {
+    let app = TestApp::new(AppState::in_memory());
+    for name in ["Write the report", "Write the summary", "Review the report"] {
+        app.create_task(named(name)).await;
+    }
+    let page = app.get("/tasks/search?q=write&limit=1").await;
+    let cursor = page.body["meta"]["next_cursor"].as_str().unwrap();
+
+    let next_page = app
+        .get(&format!("/tasks/search?q=write&limit=1&cursor={cursor}"))
+        .await;
+    assert_eq!(next_page.status, StatusCode::OK);
+    assert_eq!(next_page.data().as_array().unwrap().len(), 1);
+
+    let response = app
+        .get(&format!("/tasks/search?q=report&limit=1&cursor={cursor}"))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn task_names_cannot_carry_the_highlight_markers() {
+    let app = TestApp::new(AppState::in_memory());
+    let task_id = app.create_task(named("Write the report")).await;
+
+    for response in [
+        app.post("/tasks", named("Write\u{2} the report")).await,
+        app.patch(&format!("/tasks/{task_id}"), named("Write\u{3} the report"))
+            .await,
+    ] {
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["errors"][0]["code"], "control_character");
+    }
+
+    let response = app.get("/tasks/search?q=write").await;
+    assert_eq!(
+        response.data()[0]["snippet"],
+        "<mark>Write</mark> the report"
+    );
+}
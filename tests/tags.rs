@@ -0,0 +1,170 @@
+//! Tags through the router: their lifecycle, merges and the tag filters of `GET /tasks`.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, named};
+use first_axum_postgres_crud::AppState;
+use serde_json::{Value, json};
+
+fn app() -> TestApp {
+    TestApp::new(AppState::in_memory())
+}
+
+/// Creates a tag and returns its ID.
+async fn create_tag(app: &TestApp, name: &str) -> i64 {
+    let response = app.post("/tags", json!({ "name": name })).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.data()["tag_id"].as_i64().unwrap()
+}
+
+/// IDs of the tasks listed by `uri`, which has to succeed.
+async fn task_ids(app: &TestApp, uri: &str) -> Vec<i64> {
+    let response = app.get(uri).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["task_id"].as_i64().unwrap())
+        .collect()
+}
+
+fn tag_names(task: &Value) -> Vec<&str> {
+    task["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tag| tag["name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn tags_can_be_created_renamed_and_deleted() {
+    let app = app();
+
+    let response = app.post("/tags", json!({ "name": "urgent" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["color"], "#808080");
+    let tag_id = response.data()["tag_id"].as_i64().unwrap();
+
+    let response = app
+        .patch(
+            &format!("/tags/{tag_id}"),
+            json!({ "name": "critical", "color": "#d73a4a" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["name"], "critical");
+    assert_eq!(response.data()["color"], "#d73a4a");
+
+    let task_id = app.create_task(named("Write the report")).await;
+    app.put(&format!("/tasks/{task_id}/tags/{tag_id}")).await;
+    assert_eq!(tag_names(app.task(task_id).await.data()), ["critical"]);
+
+    assert_eq!(
+        app.delete(&format!("/tags/{tag_id}")).await.status,
+        StatusCode::OK
+    );
+    let response = app.get(&format!("/tags/{tag_id}")).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.code(), "tag_not_found");
+    assert!(tag_names(app.task(task_id).await.data()).is_empty());
+}
+
+#[tokio::test]
+async fn tag_names_are_unique_regardless_of_case() {
+    let app = app();
+    create_tag(&app, "urgent").await;
+    let other_id = create_tag(&app, "home").await;
+
+    let response = app.post("/tags", json!({ "name": "Urgent" })).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "conflict");
+
+    let response = app
+        .patch(&format!("/tags/{other_id}"), json!({ "name": "URGENT" }))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "conflict");
+}
+
+#[tokio::test]
+async fn tag_names_cannot_have_surrounding_whitespace() {
+    let app = app();
+    let tag_id = create_tag(&app, "urgent").await;
+
+    for response in [
+        app.post("/tags", json!({ "name": " home" })).await,
+        app.patch(&format!("/tags/{tag_id}"), json!({ "name": "urgent " }))
+            .await,
+    ] {
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.code(), "validation_failed");
+        assert_eq!(response.body["errors"][0]["code"], "whitespace");
+    }
+}
+
+#[tokio::test]
+async fn merging_a_tag_moves_its_tasks_over() {
+    let app = app();
+    let merged_id = create_tag(&app, "asap").await;
+    let kept_id = create_tag(&app, "urgent").await;
+    let first_id = app.create_task(named("Write the report")).await;
+    let second_id = app.create_task(named("Review the report")).await;
+    app.put(&format!("/tasks/{first_id}/tags/{merged_id}"))
+        .await;
+    app.put(&format!("/tasks/{second_id}/tags/{merged_id}"))
+        .await;
+    app.put(&format!("/tasks/{second_id}/tags/{kept_id}")).await;
+
+    let response = app
+        .post(
+            &format!("/tags/{merged_id}/merge"),
+            json!({ "into": kept_id }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["name"], "urgent");
+    assert_eq!(
+        app.get(&format!("/tags/{merged_id}")).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(tag_names(app.task(first_id).await.data()), ["urgent"]);
+    assert_eq!(tag_names(app.task(second_id).await.data()), ["urgent"]);
+
+    let response = app
+        .post(
+            &format!("/tags/{kept_id}/merge"),
+            json!({ "into": kept_id }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn tag_filters_ignore_case_and_surrounding_whitespace() {
+    let app = app();
+    let urgent_id = create_tag(&app, "Urgent").await;
+    let home_id = create_tag(&app, "home").await;
+    let both_id = app.create_task(named("Fix the roof")).await;
+    let urgent_only_id = app.create_task(named("File taxes")).await;
+    app.create_task(named("Read a book")).await;
+    app.put(&format!("/tasks/{both_id}/tags/{urgent_id}")).await;
+    app.put(&format!("/tasks/{both_id}/tags/{home_id}")).await;
+    app.put(&format!("/tasks/{urgent_only_id}/tags/{urgent_id}"))
+        .await;
+
+    assert_eq!(
+        task_ids(&app, "/tasks?tags_any=%20urgent%20,HOME").await,
+        [both_id, urgent_only_id]
+    );
+    assert_eq!(
+        task_ids(&app, "/tasks?tags_all=URGENT,%20home").await,
+        [both_id]
+    );
+}
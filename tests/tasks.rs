@@ -0,0 +1,311 @@
+//! The tasks API driven in-process through the router, over the in-memory backend.
+
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, StatusCode, header},
+};
+use common::{TestApp, named, request, with_json};
+use first_axum_postgres_crud::{AppState, build_router};
+use serde_json::json;
+
+fn app() -> TestApp {
+    TestApp::new(AppState::in_memory())
+}
+
+#[tokio::test]
+async fn created_task_can_be_read_back() {
+    let app = app();
+    let task_id = app
+        .create_task(json!({ "name": "Write the report", "priority": 3 }))
+        .await;
+
+    let response = app.task(task_id).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["name"], "Write the report");
+    assert_eq!(response.data()["priority"], 3);
+}
+
+#[tokio::test]
+async fn tasks_router_can_be_nested_anywhere() {
+    let app = TestApp::with_router(
+        Router::new().nest("/api/v1/tasks", build_router(AppState::in_memory())),
+    );
+
+    let response = app.post("/api/v1/tasks", named("Write the report")).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get("/api/v1/tasks").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data().as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn cursor_pages_cover_every_task_once() {
+    let app = app();
+    for name in ["One", "Two", "Three", "Four", "Five"] {
+        app.create_task(named(name)).await;
+    }
+
+    let mut seen = Vec::new();
+    let mut uri = "/tasks?limit=2".to_owned();
+    loop {
+        let page = app.get(&uri).await;
+        assert_eq!(page.status, StatusCode::OK);
+        assert_eq!(page.body["meta"]["total"], 5);
+
+        for task in page.data().as_array().unwrap() {
+            seen.push(task["task_id"].as_i64().unwrap());
+        }
+
+        match page.body["meta"]["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/tasks?limit=2&cursor={cursor}"),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, [1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn cursor_and_offset_cannot_be_combined() {
+    let app = app();
+    for name in ["One", "Two"] {
+        app.create_task(named(name)).await;
+    }
+    let page = app.get("/tasks?limit=1").await;
+    let cursor = page.body["meta"]["next_cursor"].as_str().unwrap();
+
+    let response = app
+        .get(&format!("/tasks?limit=1&offset=1&cursor={cursor}"))
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn tasks_can_be_sorted_and_filtered() {
+    let app = app();
+    for (name, priority) in [("Low", 1), ("High", 9), ("Middle", 5)] {
+        app.create_task(json!({ "name": name, "priority": priority }))
+            .await;
+    }
+
+    let response = app.get("/tasks?sort=-priority&priority_min=5").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let names: Vec<&str> = response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["High", "Middle"]);
+
+    let response = app.get("/tasks?sort=due").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn invalid_path_and_query_parameters_are_problems() {
+    let app = app();
+
+    let response = app.get("/tasks/abc").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    assert_eq!(response.code(), "invalid_path");
+
+    let response = app.get("/tasks?limit=abc").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    assert_eq!(response.code(), "invalid_query");
+}
+
+#[tokio::test]
+async fn missing_task_is_not_found() {
+    let response = app().get("/tasks/42").await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.code(), "task_not_found");
+}
+
+#[tokio::test]
+async fn merge_patch_clears_null_fields_and_leaves_absent_ones() {
+    let app = app();
+    let task_id = app
+        .create_task(json!({ "name": "Write the report", "priority": 3 }))
+        .await;
+
+    let response = app
+        .patch(&format!("/tasks/{task_id}"), json!({ "priority": null }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["name"], "Write the report");
+    assert_eq!(response.data()["priority"], json!(null));
+}
+
+#[tokio::test]
+async fn merge_patch_rejects_unknown_fields() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+
+    let response = app
+        .patch(&format!("/tasks/{task_id}"), json!({ "status": "done" }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.code(), "invalid_body");
+}
+
+#[tokio::test]
+async fn put_rejects_unknown_fields() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+
+    let response = app
+        .send(with_json(
+            request(Method::PUT, &format!("/tasks/{task_id}")),
+            json!({ "name": "Write the report", "status": "done" }),
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.code(), "invalid_body");
+    assert_eq!(app.task(task_id).await.data()["status"], "todo");
+}
+
+#[tokio::test]
+async fn json_patch_adding_an_unknown_field_is_rejected() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+
+    let response = app
+        .send(
+            request(Method::PATCH, &format!("/tasks/{task_id}"))
+                .header(header::CONTENT_TYPE, "application/json-patch+json")
+                .body(Body::from(
+                    json!([{ "op": "add", "path": "/status", "value": "done" }]).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.code(), "invalid_body");
+    assert_eq!(app.task(task_id).await.data()["status"], "todo");
+}
+
+#[tokio::test]
+async fn empty_merge_patch_keeps_the_version() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+    let etag = app.task(task_id).await.etag();
+
+    let response = app
+        .send(with_json(
+            request(Method::PATCH, &format!("/tasks/{task_id}")).header(header::IF_MATCH, &etag),
+            json!({}),
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.etag(), etag);
+}
+
+#[tokio::test]
+async fn stale_if_match_is_rejected() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+    let etag = app.task(task_id).await.etag();
+    app.patch(&format!("/tasks/{task_id}"), named("Write the summary"))
+        .await;
+
+    let response = app
+        .send(with_json(
+            request(Method::PATCH, &format!("/tasks/{task_id}")).header(header::IF_MATCH, &etag),
+            json!({ "priority": 1 }),
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(response.code(), "precondition_failed");
+}
+
+#[tokio::test]
+async fn if_none_match_answers_not_modified_until_the_task_changes() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+    let etag = app.task(task_id).await.etag();
+    let conditional_get = || {
+        request(Method::GET, &format!("/tasks/{task_id}"))
+            .header(header::IF_NONE_MATCH, &etag)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    assert_eq!(
+        app.send(conditional_get()).await.status,
+        StatusCode::NOT_MODIFIED
+    );
+
+    app.patch(&format!("/tasks/{task_id}"), json!({ "priority": 1 }))
+        .await;
+
+    assert_eq!(app.send(conditional_get()).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn create_reports_every_invalid_field() {
+    let app = app();
+
+    let response = app
+        .post("/tasks", json!({ "name": " ", "priority": 11 }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.code(), "validation_failed");
+    let fields: Vec<&str> = response.body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["name", "priority"]);
+}
+
+#[tokio::test]
+async fn adding_or_deleting_a_comment_changes_the_etag() {
+    let app = app();
+    let task_id = app.create_task(named("Write the report")).await;
+    let before = app.task(task_id).await.etag();
+
+    let comment = app
+        .post(
+            &format!("/tasks/{task_id}/comments"),
+            json!({ "author": "alice", "body": "Started" }),
+        )
+        .await;
+    let commented = app.task(task_id).await;
+
+    assert_ne!(commented.etag(), before);
+    assert_eq!(commented.data()["comment_count"], 1);
+
+    let comment_id = &comment.data()["comment_id"];
+    app.delete(&format!("/tasks/{task_id}/comments/{comment_id}"))
+        .await;
+
+    assert_ne!(app.task(task_id).await.etag(), commented.etag());
+}
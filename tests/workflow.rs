@@ -0,0 +1,114 @@
+//! The task status workflow: the allowed transitions and the `completed_at` stamp.
+
+mod common;
+
+use std::{env, sync::Arc};
+
+use axum::http::StatusCode;
+use common::{TestApp, named};
+use first_axum_postgres_crud::{
+    AppState, migrations,
+    models::{CreateTaskReq, TaskStatus},
+    repository::{InMemoryTaskRepository, PgTaskRepository, SharedTaskRepository},
+};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+#[tokio::test]
+async fn transitions_the_workflow_does_not_allow_are_conflicts() {
+    let app = TestApp::new(AppState::in_memory());
+    let task_id = app.create_task(named("Write the report")).await;
+
+    app.post(&format!("/tasks/{task_id}/complete"), json!({}))
+        .await;
+    let response = app
+        .post(&format!("/tasks/{task_id}/start"), json!({}))
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "illegal_transition");
+    assert_eq!(app.task(task_id).await.data()["status"], "done");
+
+    let task_id = app.create_task(named("Review the report")).await;
+    app.post(&format!("/tasks/{task_id}/block"), json!({}))
+        .await;
+    let response = app
+        .post(&format!("/tasks/{task_id}/complete"), json!({}))
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "illegal_transition");
+}
+
+#[tokio::test]
+async fn reopening_a_completed_task_clears_completed_at() {
+    let app = TestApp::new(AppState::in_memory());
+    let task_id = app.create_task(named("Write the report")).await;
+
+    let response = app
+        .post(&format!("/tasks/{task_id}/complete"), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["status"], "done");
+    assert!(response.data()["completed_at"].is_string());
+
+    let response = app
+        .post(&format!("/tasks/{task_id}/reopen"), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["status"], "todo");
+    assert_eq!(response.data()["completed_at"], json!(null));
+}
+
+/// Completes a task, then moves it through every other status, checking that only `done`
+/// carries a `completed_at`.
+async fn completed_at_is_only_set_while_done(repository: SharedTaskRepository) {
+    let task = repository
+        .create(CreateTaskReq {
+            name: "Write the report".to_owned(),
+            priority: None,
+            due_at: None,
+            start_at: None,
+            parent_task_id: None,
+        })
+        .await
+        .unwrap();
+
+    let done = repository
+        .set_status(task.task_id, TaskStatus::Done, None)
+        .await
+        .unwrap()
+        .unwrap();
+    let completed_at = done.completed_at.expect("done without completed_at");
+    assert!(completed_at >= done.created_at);
+
+    for status in [
+        TaskStatus::Todo,
+        TaskStatus::InProgress,
+        TaskStatus::Blocked,
+        TaskStatus::Cancelled,
+    ] {
+        let task = repository
+            .set_status(task.task_id, status, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(task.completed_at, None, "{status:?} kept completed_at");
+    }
+}
+
+#[tokio::test]
+async fn in_memory_completed_at_is_only_set_while_done() {
+    completed_at_is_only_set_while_done(Arc::new(InMemoryTaskRepository::new())).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a disposable Postgres database in TEST_DATABASE_URL"]
+async fn postgres_completed_at_is_only_set_while_done() {
+    let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pg_pool = PgPoolOptions::new().connect(&database_url).await.unwrap();
+    migrations::run(&pg_pool).await.unwrap();
+
+    completed_at_is_only_set_while_done(Arc::new(PgTaskRepository::new(pg_pool))).await;
+}